@@ -0,0 +1,77 @@
+use crate::errors::*;
+use crate::loader::LoadedLibrary;
+use crate::plugin_manager::{skip_loaded, LoadReport, PluginManager};
+use std::panic;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::task::{self, JoinError};
+
+impl PluginManager {
+    /// Load a folder of plugins like [`PluginManager::load_plugins`], for hosts running on a Tokio
+    /// runtime, without blocking it while the folder is read and the libraries are opened.
+    ///
+    /// Reading the folder and opening the libraries (the dynamic linker's work, including checking
+    /// their ABI version) happens on Tokio's blocking thread pool, through
+    /// [`spawn_blocking`](tokio::task::spawn_blocking). Constructing the plugins and firing their
+    /// `on_plugin_load()` still happens in the calling task, one plugin at a time, so plugins never
+    /// have to cope with being initialised concurrently or on a different thread from the rest of
+    /// the load. This part isn't async, so a plugin with a slow `on_plugin_load()` still holds up
+    /// the task.
+    ///
+    /// The future resolves to the same [`LoadReport`] as the synchronous version. Dropping it
+    /// before it resolves loads nothing, and any libraries already opened are dropped again.
+    ///
+    /// # Panics
+    ///
+    /// This has to be polled from within a Tokio runtime.
+    ///
+    /// # Safety
+    ///
+    /// Every candidate is loaded as if by [`PluginManager::load_plugin`], so the same requirements
+    /// apply to each library in the folder. On top of that, every library has to be safe to open
+    /// from a thread other than the caller's.
+    pub async unsafe fn load_plugins_async<P: AsRef<Path>>(
+        &mut self,
+        dir: P,
+    ) -> Result<LoadReport> {
+        let dir = dir.as_ref().to_owned();
+        let loaded: Vec<PathBuf> = self.plugin_paths().map(Path::to_owned).collect();
+        let logger = self.logger.clone();
+
+        let (mut report, paths) = task::spawn_blocking(move || {
+            let mut report = LoadReport::default();
+            let mut paths = Vec::new();
+            PluginManager::scan_dir(&dir, None, &mut paths, &mut report, &logger)?;
+            skip_loaded(loaded.iter().map(PathBuf::as_path), &mut paths, &logger);
+            Ok::<_, Error>((report, paths))
+        })
+        .await
+        .map_err(joined)??;
+
+        let loader = Arc::clone(&self.loader);
+        let opened: Vec<(PathBuf, Result<Box<dyn LoadedLibrary>>)> =
+            task::spawn_blocking(move || {
+                paths
+                    .into_iter()
+                    .map(|path| {
+                        let library = unsafe { PluginManager::open(&*loader, path.as_os_str()) };
+                        (path, library)
+                    })
+                    .collect()
+            })
+            .await
+            .map_err(joined)?;
+
+        self.activate_opened(opened, &mut report)?;
+
+        Ok(report)
+    }
+}
+
+/// Turn a blocking task which didn't finish into an error, or carry on unwinding if it panicked.
+fn joined(e: JoinError) -> Error {
+    match e.try_into_panic() {
+        Ok(cause) => panic::resume_unwind(cause),
+        Err(e) => Error::from(e.to_string()),
+    }
+}
@@ -0,0 +1,200 @@
+use crate::logging::{info, warn, Logger};
+use crate::plugin_manager::log_target;
+
+/// Run `f`, one of `plugin`'s `hook` callbacks, with everything written to the process' stdout
+/// and stderr captured, and then log whatever it printed under the plugin's log target.
+///
+/// The redirection applies to the whole process, so anything printed by other threads while `f`
+/// runs is captured too, and only one callback is captured at a time. Where redirecting isn't
+/// supported, `f` is simply run as usual.
+pub(crate) fn capture<R>(plugin: &str, hook: &str, logger: &Logger, f: impl FnOnce() -> R) -> R {
+    #[cfg(unix)]
+    if let Some(redirect) = unix::Redirect::start(plugin, logger) {
+        let result = f();
+        let output = redirect.finish();
+
+        let output = output.trim_end();
+        if !output.is_empty() {
+            info!(logger, target: &log_target(plugin), "`{}` printed in {}:\n{}", plugin, hook, output);
+        }
+        return result;
+    }
+
+    #[cfg(not(unix))]
+    let _ = (plugin, hook, logger);
+    f()
+}
+
+#[cfg(unix)]
+mod unix {
+    use super::warn;
+    use crate::logging::Logger;
+    use crate::plugin_manager::log_target;
+    use std::fs::{self, File, OpenOptions};
+    use std::io::{self, Read, Seek, SeekFrom, Write};
+    use std::os::fd::AsRawFd;
+    use std::os::raw::c_int;
+    use std::os::unix::fs::OpenOptionsExt;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Mutex, MutexGuard};
+    use std::{env, process};
+
+    extern "C" {
+        fn dup(fd: c_int) -> c_int;
+        fn dup2(fd: c_int, target: c_int) -> c_int;
+        fn close(fd: c_int) -> c_int;
+    }
+
+    const STDOUT: c_int = 1;
+    const STDERR: c_int = 2;
+
+    /// Only one redirection can be in place at a time, since stdout and stderr are shared by the
+    /// whole process.
+    static REDIRECTING: Mutex<()> = Mutex::new(());
+
+    /// Stdout and stderr pointed at a temporary file, until the redirection is dropped.
+    ///
+    /// A file is used rather than a pipe, since a plugin printing more than a pipe can hold would
+    /// block forever with nobody reading the other end.
+    pub(super) struct Redirect {
+        sink: File,
+        /// Duplicates of the original stdout and stderr, to be put back afterwards.
+        saved: [c_int; 2],
+        _lock: MutexGuard<'static, ()>,
+    }
+
+    impl Redirect {
+        pub(super) fn start(plugin: &str, logger: &Logger) -> Option<Redirect> {
+            let lock = REDIRECTING.lock().unwrap_or_else(|e| e.into_inner());
+            let sink = match sink() {
+                Ok(sink) => sink,
+                Err(e) => {
+                    warn!(logger, target: &log_target(plugin), "Unable to capture output: {}", e);
+                    return None;
+                }
+            };
+
+            // Anything the host has buffered belongs to the host, not the plugin.
+            let _ = io::stdout().flush();
+            let _ = io::stderr().flush();
+
+            unsafe {
+                let saved = [dup(STDOUT), dup(STDERR)];
+                if saved.contains(&-1) {
+                    for fd in saved.into_iter().filter(|&fd| fd != -1) {
+                        close(fd);
+                    }
+                    warn!(logger, target: &log_target(plugin), "Unable to capture output");
+                    return None;
+                }
+
+                dup2(sink.as_raw_fd(), STDOUT);
+                dup2(sink.as_raw_fd(), STDERR);
+                Some(Redirect {
+                    sink,
+                    saved,
+                    _lock: lock,
+                })
+            }
+        }
+
+        /// Put stdout and stderr back, and read everything which was written in the meantime.
+        pub(super) fn finish(mut self) -> String {
+            self.restore();
+
+            let mut output = Vec::new();
+            let _ = self.sink.seek(SeekFrom::Start(0));
+            let _ = self.sink.read_to_end(&mut output);
+            String::from_utf8_lossy(&output).into_owned()
+        }
+
+        fn restore(&mut self) {
+            if self.saved == [-1, -1] {
+                return;
+            }
+
+            let _ = io::stdout().flush();
+            let _ = io::stderr().flush();
+            unsafe {
+                dup2(self.saved[0], STDOUT);
+                dup2(self.saved[1], STDERR);
+                close(self.saved[0]);
+                close(self.saved[1]);
+            }
+            self.saved = [-1, -1];
+        }
+    }
+
+    impl Drop for Redirect {
+        // Also runs if the callback panics, so the host gets its output back regardless.
+        fn drop(&mut self) {
+            self.restore();
+        }
+    }
+
+    /// Create an anonymous temporary file only the current user can read.
+    fn sink() -> io::Result<File> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let name = format!(
+            "plugin-output-{}-{}",
+            process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        );
+        let path = env::temp_dir().join(name);
+
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .mode(0o600)
+            .open(&path)?;
+        // Unix lets a file be deleted while it is open, so nobody else can get to it.
+        fs::remove_file(&path)?;
+        Ok(file)
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::logging::LogLevel;
+    use std::io::{self, Write};
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn output_is_captured_until_the_redirect_finishes() {
+        let redirect = unix::Redirect::start("printer", &Logger::default()).unwrap();
+        // `print!` goes to the test harness rather than to the process' stdout.
+        io::stdout().write_all(b"to stdout\n").unwrap();
+        io::stderr().write_all(b"to stderr\n").unwrap();
+        assert_eq!(redirect.finish(), "to stdout\nto stderr\n");
+    }
+
+    #[test]
+    fn the_callback_result_is_returned() {
+        assert_eq!(
+            capture("printer", "on_plugin_load", &Logger::default(), || 42),
+            42
+        );
+    }
+
+    #[test]
+    fn captured_output_is_logged_through_the_managers_logger() {
+        let logged = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&logged);
+        let logger = Logger::new(move |level, message: &str| {
+            sink.lock().unwrap().push((level, message.to_owned()))
+        });
+
+        capture("printer", "on_plugin_load", &logger, || {
+            io::stdout().write_all(b"hello\n").unwrap();
+        });
+        assert_eq!(
+            *logged.lock().unwrap(),
+            [(
+                LogLevel::Info,
+                "`printer` printed in on_plugin_load:\nhello".to_owned()
+            )]
+        );
+    }
+}
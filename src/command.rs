@@ -0,0 +1,31 @@
+/// A command a plugin handles on behalf of the host, e.g. a subcommand of the host's CLI, listed
+/// by [`Plugin::commands`](crate::Plugin::commands).
+///
+/// Everything in here is owned, so specs can be kept around (or shown to the user) independently
+/// of the plugin which listed them.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct CommandSpec {
+    /// What the command is invoked as, e.g. `"export"`.
+    pub name: String,
+    /// A one-line description of what the command does.
+    pub help: String,
+    /// Hints for the arguments the command takes, in order, e.g. `"<file>"` or `"[--force]"`.
+    pub args: Vec<String>,
+}
+
+impl CommandSpec {
+    /// Describe a command taking no arguments.
+    pub fn new<N: Into<String>, H: Into<String>>(name: N, help: H) -> Self {
+        Self {
+            name: name.into(),
+            help: help.into(),
+            args: Vec::new(),
+        }
+    }
+
+    /// Add a hint for the next argument the command takes.
+    pub fn arg<S: Into<String>>(mut self, hint: S) -> Self {
+        self.args.push(hint.into());
+        self
+    }
+}
@@ -0,0 +1,167 @@
+use crate::errors::*;
+use crate::event::PluginEvent;
+use crate::health::HealthStatus;
+use crate::message::PluginMessage;
+use crate::metadata::PluginMetadata;
+use crate::plugin::Plugin;
+use crate::plugin_manager::{LoadReport, PluginId, PluginManager};
+use crate::snapshot::PluginSnapshot;
+use std::ffi::OsStr;
+use std::sync::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+use std::time::Duration;
+
+/// A [`PluginManager`] behind a read-write lock, so plugins can be looked up from many threads at
+/// once while being loaded and unloaded from another.
+///
+/// Methods which only look at the plugins take a shared lock, so they never block each other, only
+/// a thread which is loading or unloading. Everything which changes the set of plugins takes an
+/// exclusive lock. So does firing callbacks taking `&mut self` (such as `on_event()`), since
+/// plugins are free to update themselves from those.
+///
+/// Plugins can't be borrowed past the lock which protects them, so lookups take a closure
+/// ([`with_plugin`](Self::with_plugin)) or return owned copies. For anything else, take the lock
+/// with [`read`](Self::read) or [`write`](Self::write) and use the [`PluginManager`] directly.
+///
+/// ```
+/// use plugin_framework::ConcurrentPluginManager;
+/// use std::sync::Arc;
+/// use std::thread;
+///
+/// let manager = Arc::new(ConcurrentPluginManager::default());
+///
+/// let reader = Arc::clone(&manager);
+/// thread::spawn(move || println!("{:?}", reader.plugin_names()))
+///     .join()
+///     .unwrap();
+/// ```
+///
+/// A thread which panics while holding the lock doesn't leave it poisoned for everyone else. The
+/// manager looks after its own consistency when plugins panic (see
+/// [`PanicPolicy`](crate::PanicPolicy)), so the lock is simply taken over.
+#[derive(Default)]
+pub struct ConcurrentPluginManager {
+    manager: RwLock<PluginManager>,
+}
+
+impl ConcurrentPluginManager {
+    /// Share `manager` between threads.
+    pub fn new(manager: PluginManager) -> Self {
+        Self {
+            manager: RwLock::new(manager),
+        }
+    }
+
+    /// Take a shared lock on the manager, blocking until nobody is changing it.
+    pub fn read(&self) -> RwLockReadGuard<'_, PluginManager> {
+        self.manager.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Take an exclusive lock on the manager, blocking until nobody else is using it.
+    pub fn write(&self) -> RwLockWriteGuard<'_, PluginManager> {
+        self.manager.write().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Stop sharing the manager, and get it back.
+    pub fn into_inner(self) -> PluginManager {
+        self.manager.into_inner().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Call `f` with the plugin called `name`, under a shared lock, returning what it returns.
+    /// Returns `None` if no plugin by that name is loaded.
+    pub fn with_plugin<R, F: FnOnce(&dyn Plugin) -> R>(&self, name: &str, f: F) -> Option<R> {
+        self.read().get_plugin(name).map(f)
+    }
+
+    /// The names of every loaded plugin, in the order they were loaded (see
+    /// [`PluginManager::plugin_names`]).
+    pub fn plugin_names(&self) -> Vec<String> {
+        self.read().plugin_names().map(str::to_owned).collect()
+    }
+
+    /// Check whether a plugin called `name` is loaded.
+    pub fn is_loaded(&self, name: &str) -> bool {
+        self.read().is_loaded(name)
+    }
+
+    /// How many plugins are loaded.
+    pub fn len(&self) -> usize {
+        self.read().len()
+    }
+
+    /// Check whether no plugins are loaded.
+    pub fn is_empty(&self) -> bool {
+        self.read().is_empty()
+    }
+
+    /// Describe every loaded plugin, as with [`PluginManager::list_metadata`].
+    pub fn list_metadata(&self) -> Vec<PluginMetadata> {
+        self.read().list_metadata()
+    }
+
+    /// Ask every loaded plugin how it is doing, as with [`PluginManager::health`].
+    pub fn health(&self) -> Vec<(String, HealthStatus)> {
+        self.read().health()
+    }
+
+    /// Take a snapshot of every loaded plugin, as with [`PluginManager::snapshot`], which can be
+    /// kept after the lock is released.
+    pub fn snapshot(&self) -> Vec<PluginSnapshot> {
+        self.read().snapshot()
+    }
+
+    /// Load a plugin, as with [`PluginManager::load_plugin`]. This holds the exclusive lock until
+    /// the plugin's `on_plugin_load()` has returned.
+    ///
+    /// # Safety
+    ///
+    /// The same requirements as for [`PluginManager::load_plugin`] apply.
+    pub unsafe fn load_plugin<P: AsRef<OsStr>>(&self, filename: P) -> Result<PluginId> {
+        self.write().load_plugin(filename)
+    }
+
+    /// Load a folder of plugins, as with [`PluginManager::load_plugins`]. This holds the exclusive
+    /// lock for the whole folder.
+    ///
+    /// # Safety
+    ///
+    /// The same requirements as for [`PluginManager::load_plugins`] apply.
+    pub unsafe fn load_plugins<P: AsRef<OsStr>>(&self, dir: P) -> Result<LoadReport> {
+        self.write().load_plugins(dir)
+    }
+
+    /// Unload the plugin called `name`, as with [`PluginManager::unload_plugin`].
+    pub fn unload_plugin(&self, name: &str) -> Result<()> {
+        self.write().unload_plugin(name)
+    }
+
+    /// Unload every plugin, as with [`PluginManager::unload`].
+    pub fn unload(&self) -> std::result::Result<(), Vec<(String, Error)>> {
+        self.write().unload()
+    }
+
+    /// Fire every enabled plugin's `update()`, as with [`PluginManager::update_all`].
+    pub fn update_all(&self, delta: Duration) {
+        self.write().update_all(delta)
+    }
+
+    /// Broadcast an event to every enabled plugin, as with [`PluginManager::dispatch_event`].
+    pub fn dispatch_event(&self, event: &PluginEvent) {
+        self.write().dispatch_event(event)
+    }
+
+    /// Deliver a message to every enabled plugin, as with [`PluginManager::send_message`].
+    pub fn send_message(&self, msg: PluginMessage) {
+        self.write().send_message(msg)
+    }
+
+    /// Deliver the messages plugins have sent, as with [`PluginManager::deliver_messages`].
+    pub fn deliver_messages(&self) {
+        self.write().deliver_messages()
+    }
+}
+
+impl From<PluginManager> for ConcurrentPluginManager {
+    fn from(manager: PluginManager) -> Self {
+        Self::new(manager)
+    }
+}
@@ -0,0 +1,28 @@
+use std::collections::HashMap;
+
+/// Startup configuration the host hands to a plugin's constructor, via
+/// [`PluginManager::load_plugin_with_config`](crate::PluginManager::load_plugin_with_config).
+///
+/// This is a simple set of string key/value pairs (a path, a feature flag, ...), which each plugin
+/// is free to interpret however it likes.
+#[derive(Debug, Clone, Default)]
+pub struct PluginConfig {
+    pub values: HashMap<String, String>,
+}
+
+impl PluginConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set `key` to `value`, returning the updated configuration so calls can be chained.
+    pub fn with<K: Into<String>, V: Into<String>>(mut self, key: K, value: V) -> Self {
+        self.values.insert(key.into(), value.into());
+        self
+    }
+
+    /// Get the value stored under `key`, if any.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(String::as_str)
+    }
+}
@@ -0,0 +1,167 @@
+use crate::message::{MessageSender, Outbox, PluginMessage};
+use crate::plugin_manager::PluginId;
+use log::{LevelFilter, Log};
+use std::any::Any;
+use std::collections::HashMap;
+
+/// Host services shared with every plugin, handed to [`Plugin::on_plugin_load`](crate::Plugin::on_plugin_load).
+///
+/// This gives plugins a way to call back into the host: they can log through the host's logger,
+/// send messages to the other plugins, and both the host and plugins can register named services
+/// (shared state, callbacks, route tables, ...) for others to look up.
+///
+/// # Note on Ownership
+///
+/// A service registered by a plugin is owned by that plugin, and is removed when the plugin is
+/// unloaded. This is because the service's code (including its destructor) lives in the plugin's
+/// library, so it can't be allowed to outlive it. Services registered by the host are never
+/// removed automatically.
+pub struct PluginContext {
+    services: HashMap<String, Service>,
+    /// The plugin currently being loaded, which owns anything registered in the meantime.
+    current: Option<PluginId>,
+    outbox: Outbox,
+}
+
+struct Service {
+    owner: Option<PluginId>,
+    value: Box<dyn Any + Send + Sync>,
+}
+
+impl PluginContext {
+    pub(crate) fn new() -> Self {
+        Self {
+            services: HashMap::new(),
+            current: None,
+            outbox: Outbox::default(),
+        }
+    }
+
+    /// The host's logger.
+    ///
+    /// Each plugin library gets its own copy of the `log` crate's global state, so the `log`
+    /// macros inside a plugin don't reach the host's logger unless the plugin forwards to this
+    /// one (e.g. by calling `log::set_logger` with it from `on_plugin_load`, along with
+    /// `log::set_max_level` with [`PluginContext::max_level`]).
+    ///
+    /// This is looked up each time, so it is whatever logger the host has installed by then.
+    pub fn logger(&self) -> &'static dyn Log {
+        log::logger()
+    }
+
+    /// The most verbose level the host's logger is interested in, so plugins forwarding to
+    /// [`PluginContext::logger`] can skip building messages it would throw away.
+    pub fn max_level(&self) -> LevelFilter {
+        log::max_level()
+    }
+
+    /// A sender for the plugin being loaded to send messages to the other plugins with, which it
+    /// can keep for as long as it is loaded.
+    pub fn message_sender(&self) -> MessageSender {
+        MessageSender::new(self.current, Outbox::clone(&self.outbox))
+    }
+
+    /// Take the oldest message sent through a [`MessageSender`], along with who sent it.
+    pub(crate) fn next_message(&self) -> Option<(Option<PluginId>, PluginMessage)> {
+        self.outbox
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .pop_front()
+    }
+
+    /// Register a service under `name`, replacing any existing service with that name.
+    pub fn register<S: Into<String>, T: Any + Send + Sync>(&mut self, name: S, service: T) {
+        self.services.insert(
+            name.into(),
+            Service {
+                owner: self.current,
+                value: Box::new(service),
+            },
+        );
+    }
+
+    /// Look up the service registered under `name`, if there is one of type `T`.
+    pub fn get<T: Any>(&self, name: &str) -> Option<&T> {
+        self.services
+            .get(name)
+            .and_then(|service| service.value.downcast_ref())
+    }
+
+    /// Look up the service registered under `name` for mutation, if there is one of type `T`.
+    pub fn get_mut<T: Any>(&mut self, name: &str) -> Option<&mut T> {
+        self.services
+            .get_mut(name)
+            .and_then(|service| service.value.downcast_mut())
+    }
+
+    /// Remove the service registered under `name`, returning whether there was one.
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.services.remove(name).is_some()
+    }
+
+    /// Mark `owner` as the plugin responsible for anything registered until this is called again.
+    pub(crate) fn set_current(&mut self, owner: Option<PluginId>) {
+        self.current = owner;
+    }
+
+    /// An empty context sharing this one's outbox, to load a plugin in on another thread and then
+    /// [`merge`](PluginContext::merge) back, so whatever it sends in the meantime isn't lost.
+    pub(crate) fn fork(&self) -> Self {
+        Self {
+            outbox: Outbox::clone(&self.outbox),
+            ..Self::new()
+        }
+    }
+
+    /// Drop every message sent through a [`MessageSender`] which hasn't been delivered yet.
+    pub(crate) fn clear_messages(&mut self) {
+        self.outbox
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+
+    /// Move every service registered in `other` into this context, replacing any with the same
+    /// name.
+    pub(crate) fn merge(&mut self, other: PluginContext) {
+        self.services.extend(other.services);
+    }
+
+    /// Drop every service registered by `owner`.
+    pub(crate) fn remove_owned_by(&mut self, owner: PluginId) {
+        self.services
+            .retain(|_, service| service.owner != Some(owner));
+    }
+
+    /// Drop every service registered by a plugin, keeping the ones registered by the host.
+    pub(crate) fn remove_plugin_services(&mut self) {
+        self.services.retain(|_, service| service.owner.is_none());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn max_level_follows_the_host() {
+        let context = PluginContext::new();
+        log::set_max_level(LevelFilter::Warn);
+        assert_eq!(context.max_level(), LevelFilter::Warn);
+        log::set_max_level(LevelFilter::Trace);
+        assert_eq!(context.max_level(), LevelFilter::Trace);
+    }
+
+    #[test]
+    fn plugin_services_are_removed_with_their_owner() {
+        let mut context = PluginContext::new();
+        context.register("host", 1u32);
+        context.set_current(Some(PluginId(7)));
+        context.register("plugin", 2u32);
+        context.set_current(None);
+
+        context.remove_owned_by(PluginId(7));
+        assert_eq!(context.get::<u32>("host"), Some(&1));
+        assert_eq!(context.get::<u32>("plugin"), None);
+    }
+}
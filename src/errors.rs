@@ -1,5 +1,220 @@
+use crate::plugin_manager::PluginId;
+use std::path::PathBuf;
+use std::time::Duration;
+
 error_chain! {
     types {
         Error, ErrorKind, ResultExt, Result;
     }
+
+    errors {
+        /// A plugin directory (or one of its entries) couldn't be read.
+        DirectoryUnreadable(path: PathBuf) {
+            description("unable to read the plugin directory")
+            display("Unable to read the plugin directory {:?}", path)
+        }
+
+        /// The shared library couldn't be opened. The underlying OS error is kept as the cause.
+        LibraryLoadFailed(path: PathBuf) {
+            description("unable to load the plugin library")
+            display("Unable to load the plugin library {:?}", path)
+        }
+
+        /// A symbol the host needs wasn't exported by the plugin library.
+        SymbolNotFound(symbol: String) {
+            description("a required symbol wasn't found in the plugin library")
+            display("The `{}` symbol wasn't found", symbol)
+        }
+
+        /// The plugin was built against an incompatible version of this crate.
+        AbiMismatch(found: u32, expected: u32) {
+            description("the plugin was built against an incompatible ABI version")
+            display(
+                "The plugin was built against ABI version {}, but the host expects version {}",
+                found,
+                expected
+            )
+        }
+
+        /// A plugin with the same name is already loaded.
+        DuplicatePlugin(name: String) {
+            description("duplicate plugin name")
+            display("Duplicate plugin name: a plugin named `{}` is already loaded", name)
+        }
+
+        /// Plugins are already loaded from the same file, however its path was spelled.
+        AlreadyLoaded(path: PathBuf) {
+            description("the plugin library is already loaded")
+            display("The plugin library {:?} is already loaded", path)
+        }
+
+        /// The plugin is on the manager's denylist, or missing from its allowlist.
+        PluginRejected(name: String) {
+            description("the plugin isn't allowed to be loaded")
+            display("The plugin `{}` isn't allowed to be loaded", name)
+        }
+
+        /// An [interceptor](crate::PluginManager::register_interceptor) vetoed the plugin.
+        PluginVetoed(name: String, reason: String) {
+            description("the plugin was vetoed")
+            display("The plugin `{}` was vetoed: {}", name, reason)
+        }
+
+        /// No loaded plugin has the given name.
+        PluginNotFound(name: String) {
+            description("no plugin with that name is loaded")
+            display("No plugin named `{}` is loaded", name)
+        }
+
+        /// No loaded plugin has the given id.
+        PluginIdNotFound(id: PluginId) {
+            description("no plugin with that id is loaded")
+            display("No plugin with id {} is loaded", id)
+        }
+
+        /// A plugin depends on another plugin which isn't loaded.
+        MissingDependency(plugin: String, needs: String) {
+            description("a plugin depends on a plugin which isn't loaded")
+            display("The plugin `{}` depends on `{}`, which isn't loaded", plugin, needs)
+        }
+
+        /// The dependencies of a set of plugins form a cycle, listed starting and ending with the
+        /// same plugin. Plugins from the same library are listed together, joined with `+`.
+        DependencyCycle(plugins: Vec<String>) {
+            description("the plugin dependencies form a cycle")
+            display("The plugin dependencies form a cycle: {}", plugins.join(" -> "))
+        }
+
+        /// The plugin's `on_plugin_load()` returned an error, flattened into `reason`.
+        PluginLoadFailed(name: String, reason: String) {
+            description("the plugin failed to load")
+            display("The plugin `{}` failed to load: {}", name, reason)
+        }
+
+        /// The plugin's `on_plugin_unload()` returned an error, flattened into `reason`.
+        PluginUnloadFailed(name: String, reason: String) {
+            description("the plugin failed to unload cleanly")
+            display("The plugin `{}` failed to unload cleanly: {}", name, reason)
+        }
+
+        /// The plugin's `_plugin_create_v2` constructor declined to load into this version of the
+        /// host.
+        IncompatibleHost(path: PathBuf, host_version: u32) {
+            description("the plugin doesn't support this version of the host")
+            display("The plugin library {:?} doesn't support host version {}", path, host_version)
+        }
+
+        /// The plugin was built against a version of the plugin API the host doesn't provide (see
+        /// [`PLUGIN_API_VERSION`](crate::PLUGIN_API_VERSION)). If the plugin's version couldn't
+        /// be parsed, the underlying error is kept as the cause.
+        IncompatibleApi(plugin: PathBuf, required: String, host: String) {
+            description("the plugin was built against an incompatible plugin API version")
+            display(
+                "The plugin library {:?} needs version {} of the plugin API, but the host provides {}",
+                plugin, required, host
+            )
+        }
+
+        /// The plugin's constructor returned a null pointer instead of a plugin.
+        ConstructorReturnedNull(path: PathBuf) {
+            description("the plugin constructor returned null")
+            display("The plugin constructor in {:?} returned a null pointer", path)
+        }
+
+        /// The plugin's constructor panicked.
+        ConstructorPanicked(message: String) {
+            description("the plugin constructor panicked")
+            display("The plugin constructor panicked: {}", message)
+        }
+
+        /// The plugin panicked inside one of its callbacks.
+        PluginPanicked(name: String, message: String) {
+            description("the plugin panicked")
+            display("The plugin `{}` panicked: {}", name, message)
+        }
+
+        /// The library's `_plugin_create_all` constructor didn't create any plugins.
+        NoPlugins(path: PathBuf) {
+            description("the plugin library didn't create any plugins")
+            display("The plugin library {:?} didn't create any plugins", path)
+        }
+
+        /// Loading a plugin didn't finish within the time it was given.
+        LoadTimeout(path: PathBuf, timeout: Duration) {
+            description("the plugin took too long to load")
+            display("The plugin at {:?} didn't finish loading within {:?}", path, timeout)
+        }
+
+        /// The plugin's `on_plugin_unload()` didn't finish within the time it was given.
+        UnloadTimeout(name: String, timeout: Duration) {
+            description("the plugin took too long to unload")
+            display("The plugin `{}` didn't finish unloading within {:?}", name, timeout)
+        }
+
+        /// No loaded plugin handles a command by this name.
+        UnknownCommand(name: String) {
+            description("unknown command")
+            display("No loaded plugin handles the command `{}`", name)
+        }
+
+        /// A plugin's `run_command()` returned an error.
+        CommandFailed(plugin: String, command: String, reason: String) {
+            description("a plugin command failed")
+            display("The command `{}` of the plugin `{}` failed: {}", command, plugin, reason)
+        }
+
+        /// A plugin library's signature didn't check out against the trusted public key, or
+        /// wasn't a valid signature at all.
+        SignatureInvalid(path: PathBuf) {
+            description("the plugin library's signature is invalid")
+            display("The plugin library {:?} isn't validly signed by the trusted key", path)
+        }
+
+        /// A plugin library's checksum didn't match the one it was expected to have.
+        IntegrityCheckFailed(path: PathBuf, expected: String, found: String) {
+            description("the plugin library failed its integrity check")
+            display(
+                "The plugin library {:?} has the SHA-256 checksum {}, but {} was expected",
+                path, found, expected
+            )
+        }
+
+        /// A plugin library's export table couldn't be read. The underlying error is kept as the
+        /// cause.
+        SymbolTableUnreadable(path: PathBuf) {
+            description("unable to read the symbols exported by the plugin library")
+            display("Unable to read the symbols exported by {:?}", path)
+        }
+
+        /// A plugin manifest couldn't be read, or isn't valid. The underlying error is kept as the
+        /// cause.
+        ManifestInvalid(path: PathBuf) {
+            description("unable to read the plugin manifest")
+            display("Unable to read the plugin manifest {:?}", path)
+        }
+
+        /// A manifest entry points at a file which doesn't exist.
+        ManifestEntryMissing(name: String, path: PathBuf) {
+            description("a plugin listed in the manifest doesn't exist")
+            display("The manifest entry `{}` points at {:?}, which doesn't exist", name, path)
+        }
+
+        /// The library a manifest entry points at doesn't provide the plugin the entry names.
+        ManifestEntryMismatch(name: String, path: PathBuf) {
+            description("a plugin library doesn't provide the plugin the manifest says it does")
+            display("The manifest entry `{}` points at {:?}, which doesn't provide it", name, path)
+        }
+
+        /// A plugin directory couldn't be watched for changes.
+        WatchFailed(path: PathBuf) {
+            description("unable to watch for plugin changes")
+            display("Unable to watch {:?} for plugin changes", path)
+        }
+
+        /// The old instance of a plugin was unloaded, but its replacement couldn't be loaded.
+        ReloadFailed(name: String) {
+            description("the plugin was unloaded, but its replacement failed to load")
+            display("The plugin `{}` was unloaded, but its replacement failed to load", name)
+        }
+    }
 }
@@ -0,0 +1,35 @@
+use std::any::Any;
+
+/// An event broadcast by the host to every loaded plugin, through
+/// [`PluginManager::dispatch_event`](crate::PluginManager::dispatch_event).
+///
+/// Events are identified by a `topic` (e.g. `"config_reloaded"`), and can optionally carry a
+/// payload of any type, which plugins recover by downcasting.
+#[derive(Debug)]
+pub struct PluginEvent {
+    pub topic: String,
+    pub payload: Option<Box<dyn Any + Send + Sync>>,
+}
+
+impl PluginEvent {
+    /// Create an event with the given topic and no payload.
+    pub fn new<S: Into<String>>(topic: S) -> Self {
+        Self {
+            topic: topic.into(),
+            payload: None,
+        }
+    }
+
+    /// Create an event with the given topic, carrying `payload`.
+    pub fn with_payload<S: Into<String>, T: Any + Send + Sync>(topic: S, payload: T) -> Self {
+        Self {
+            topic: topic.into(),
+            payload: Some(Box::new(payload)),
+        }
+    }
+
+    /// Get the event's payload, if it has one of type `T`.
+    pub fn payload<T: Any>(&self) -> Option<&T> {
+        self.payload.as_ref().and_then(|p| p.downcast_ref())
+    }
+}
@@ -0,0 +1,23 @@
+/// How a plugin reports it is doing, from [`Plugin::health_check`](crate::Plugin::health_check).
+///
+/// The messages are owned, so statuses can be kept around (or sent elsewhere) independently of
+/// the plugin which reported them.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum HealthStatus {
+    /// Everything is working as expected.
+    #[default]
+    Ok,
+    /// The plugin is working, but not as well as it should, e.g. because a service it relies on
+    /// is slow to respond.
+    Degraded(String),
+    /// The plugin can't do its job.
+    Unhealthy(String),
+}
+
+impl HealthStatus {
+    /// Check whether the status is [`HealthStatus::Ok`].
+    pub fn is_ok(&self) -> bool {
+        matches!(self, HealthStatus::Ok)
+    }
+}
@@ -0,0 +1,44 @@
+use crate::errors::*;
+use crate::plugin_manager::PluginManager;
+use object::{BinaryFormat, NameOrOrdinal, Object};
+use std::fs;
+use std::path::Path;
+
+impl PluginManager {
+    /// List the names of the symbols a plugin library exports, e.g. to check it provides
+    /// everything the host is going to look up before loading it.
+    ///
+    /// The library's export table is read straight from the file (ELF on Linux, Mach-O on macOS
+    /// and PE on Windows, whichever platform the library was built for), so the library is never
+    /// opened and none of its code runs. Symbols are listed under the name they would be looked up
+    /// with, e.g. by [`PluginManager::get_symbol`], so the leading underscore Mach-O adds to every
+    /// symbol is left out. Symbols a PE library only exports by ordinal can't be looked up by name,
+    /// so they aren't listed.
+    ///
+    /// An error is returned if the file can't be read, or isn't a library in a format we
+    /// recognise.
+    pub fn list_symbols<P: AsRef<Path>>(path: P) -> Result<Vec<String>> {
+        let path = path.as_ref();
+        let unreadable = || ErrorKind::SymbolTableUnreadable(path.into());
+
+        let data = fs::read(path).chain_err(unreadable)?;
+        let file = object::File::parse(&*data).chain_err(unreadable)?;
+        let exports = file.exports().chain_err(unreadable)?;
+
+        let mach_o = file.format() == BinaryFormat::MachO;
+        let mut symbols = Vec::new();
+        for export in exports {
+            let export = export.chain_err(unreadable)?;
+            let NameOrOrdinal::Name(name) = export.name() else {
+                continue;
+            };
+            let name = match name.strip_prefix(b"_") {
+                Some(stripped) if mach_o => stripped,
+                _ => name,
+            };
+            symbols.push(String::from_utf8_lossy(name).into_owned());
+        }
+
+        Ok(symbols)
+    }
+}
@@ -1,9 +1,66 @@
+// `error_chain!` expands recursively, once per error kind.
+#![recursion_limit = "256"]
+
+#[cfg(feature = "async")]
+mod asynchronous;
+#[cfg(feature = "capture")]
+mod capture;
+mod command;
+mod concurrent;
+mod config;
+mod context;
 mod errors;
+mod event;
+mod health;
+#[cfg(feature = "introspect")]
+mod introspect;
+mod limits;
+mod loader;
+mod logging;
+#[cfg(feature = "manifest")]
+mod manifest;
+mod memory;
+mod message;
+mod metadata;
+mod panic_hook;
 mod plugin;
 mod plugin_manager;
+#[cfg(feature = "semver")]
+mod semver_check;
+#[cfg(feature = "signature")]
+mod signature;
+mod snapshot;
+mod timings;
+#[cfg(feature = "verify")]
+mod verify;
+#[cfg(feature = "wasm")]
+mod wasm;
+#[cfg(feature = "watch")]
+mod watch;
 
 #[macro_use]
 extern crate error_chain;
 
-pub use plugin::Plugin;
-pub use plugin_manager::PluginManager;
+pub use command::CommandSpec;
+pub use concurrent::ConcurrentPluginManager;
+pub use config::PluginConfig;
+pub use context::PluginContext;
+pub use errors::{Error, ErrorKind, Result, ResultExt};
+pub use event::PluginEvent;
+pub use health::HealthStatus;
+pub use limits::ResourceLimits;
+pub use loader::{LibraryLoader, LoadedLibrary, NativeLoader, Symbol};
+pub use logging::LogLevel;
+pub use message::{MessageSender, PluginMessage};
+pub use metadata::PluginMetadata;
+pub use plugin::{Plugin, PluginRegistrar, CORE_ABI_VERSION, PLUGIN_API_VERSION};
+pub use plugin_manager::{
+    DrainedPlugins, LoadReport, PanicPolicy, PluginId, PluginManager, PluginManagerBuilder,
+};
+pub use snapshot::PluginSnapshot;
+pub use timings::{HookTimings, PluginTimings};
+
+/// The public half of an ed25519 key plugin libraries are signed with, as taken by
+/// [`PluginManager::load_plugin_signed`].
+#[cfg(feature = "signature")]
+pub use ed25519_dalek::VerifyingKey;
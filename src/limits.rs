@@ -0,0 +1,81 @@
+use std::time::Duration;
+
+/// Limits on what plugins may use while being called, set with
+/// [`PluginManagerBuilder::resource_limits`](crate::PluginManagerBuilder::resource_limits).
+///
+/// These are checked once a call has returned, so they are advisory rather than a security
+/// boundary: a native plugin runs in the host's own process with all of its privileges, and the
+/// manager can't stop it mid-call. A plugin which overruns a limit is logged and counted (see
+/// [`HookTimings::over_budget`](crate::HookTimings::over_budget)), and may be poisoned so it isn't
+/// called again, but one which never returns still hangs its caller. Use
+/// [`PluginManager::load_plugin_with_timeout`](crate::PluginManager::load_plugin_with_timeout)
+/// and [`PluginManager::unload_with_timeout`](crate::PluginManager::unload_with_timeout) to get
+/// away from a plugin which hangs, or run untrusted code out of process.
+///
+/// ```
+/// use plugin_framework::{PluginManager, ResourceLimits};
+/// use std::time::Duration;
+///
+/// let manager = PluginManager::builder()
+///     .resource_limits(
+///         ResourceLimits::new()
+///             .max_call_duration(Duration::from_millis(50))
+///             .poison_on_violation(true),
+///     )
+///     .build();
+/// ```
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub struct ResourceLimits {
+    max_call_duration: Option<Duration>,
+    poison_on_violation: bool,
+}
+
+impl ResourceLimits {
+    /// Start without any limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limit how long a single call into a plugin may take, covering `on_plugin_load()`,
+    /// `on_plugin_unload()`, and the callbacks fired by
+    /// [`update_all`](crate::PluginManager::update_all),
+    /// [`dispatch_event`](crate::PluginManager::dispatch_event) and
+    /// [`send_message`](crate::PluginManager::send_message). By default calls may take as long as
+    /// they like.
+    pub fn max_call_duration(mut self, limit: Duration) -> Self {
+        self.max_call_duration = Some(limit);
+        self
+    }
+
+    /// Choose whether a plugin which overruns a limit is poisoned, the same as if it had
+    /// panicked, so it isn't called again until its poison is
+    /// [cleared](crate::PluginManager::clear_poison). A plugin overrunning its `on_plugin_load()`
+    /// is still loaded, but poisoned straight away. A plugin which is being unloaded anyway is only
+    /// logged. Defaults to `false`, where overruns are only logged and counted.
+    pub fn poison_on_violation(mut self, poison: bool) -> Self {
+        self.poison_on_violation = poison;
+        self
+    }
+
+    /// Check whether a call which took `elapsed` overran the limits.
+    pub(crate) fn exceeded_by(&self, elapsed: Duration) -> bool {
+        self.max_call_duration.is_some_and(|limit| elapsed > limit)
+    }
+
+    pub(crate) fn poisons(&self) -> bool {
+        self.poison_on_violation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_calls_longer_than_the_limit_overrun_it() {
+        let limits = ResourceLimits::new().max_call_duration(Duration::from_millis(10));
+        assert!(!limits.exceeded_by(Duration::from_millis(10)));
+        assert!(limits.exceeded_by(Duration::from_millis(11)));
+        assert!(!ResourceLimits::new().exceeded_by(Duration::MAX));
+    }
+}
@@ -0,0 +1,183 @@
+use crate::errors::*;
+use libloading::Library;
+use std::any::Any;
+use std::ffi::{c_void, OsStr};
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem;
+use std::ops::Deref;
+
+/// Opens plugin libraries on behalf of a [`PluginManager`](crate::PluginManager).
+///
+/// The default, [`NativeLoader`], opens real shared libraries with `libloading`. Supplying a
+/// different loader through [`PluginManager::with_loader`](crate::PluginManager::with_loader)
+/// makes it possible to exercise the manager's load/unload bookkeeping without building actual
+/// `.so` files, e.g. by handing back libraries whose symbols point at functions in the test
+/// binary itself.
+pub trait LibraryLoader: Send + Sync {
+    /// Open the library at `path`.
+    ///
+    /// # Safety
+    ///
+    /// Opening a native library runs its initialisation routines, so the same care as
+    /// `libloading::Library::new` is required.
+    unsafe fn load(&self, path: &OsStr) -> Result<Box<dyn LoadedLibrary>>;
+}
+
+/// A library opened by a [`LibraryLoader`], which plugins are constructed from.
+///
+/// The library is kept alive for as long as any plugin created from it, and dropped afterwards.
+/// Libraries are `Any`, so the manager can recognise its own kinds of library.
+pub trait LoadedLibrary: Any + Send + Sync {
+    /// Look up the address of the symbol exported as `name`.
+    ///
+    /// For a function this is the function itself, and for a static it is a pointer to its value,
+    /// just like `dlsym`. A missing symbol should be reported as [`ErrorKind::SymbolNotFound`].
+    ///
+    /// # Safety
+    ///
+    /// The address is only valid for as long as the library is alive, and the caller is trusted
+    /// to know the symbol's real type.
+    unsafe fn symbol(&self, name: &[u8]) -> Result<*const c_void>;
+}
+
+/// The default [`LibraryLoader`], which opens native shared libraries from disk.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct NativeLoader;
+
+impl LibraryLoader for NativeLoader {
+    unsafe fn load(&self, path: &OsStr) -> Result<Box<dyn LoadedLibrary>> {
+        let library = Library::new(path).chain_err(|| ErrorKind::LibraryLoadFailed(path.into()))?;
+        Ok(Box::new(library))
+    }
+}
+
+impl LoadedLibrary for Library {
+    unsafe fn symbol(&self, name: &[u8]) -> Result<*const c_void> {
+        let symbol = self
+            .get::<*const c_void>(name)
+            .chain_err(|| ErrorKind::SymbolNotFound(String::from_utf8_lossy(name).into_owned()))?;
+        Ok(*symbol)
+    }
+}
+
+/// A symbol looked up in a loaded plugin library, typed as `T`.
+///
+/// Like `libloading::Symbol`, this borrows from whatever owns the library, so the symbol can't be
+/// used after its library has been unloaded. It dereferences to the symbol itself, so a function
+/// can be called with `(*symbol)(...)`, or directly as `symbol(...)`.
+pub struct Symbol<'lib, T> {
+    value: T,
+    library: PhantomData<&'lib dyn LoadedLibrary>,
+}
+
+impl<T> Symbol<'_, T> {
+    /// Wrap the raw address of a symbol as a `T`, which must be pointer sized (a function pointer,
+    /// or a raw pointer to a static). Any other `T` fails to compile.
+    pub(crate) unsafe fn new(address: *const c_void) -> Self {
+        const {
+            assert!(
+                mem::size_of::<T>() == mem::size_of::<*const c_void>(),
+                "a symbol can only be read as a pointer sized type"
+            )
+        };
+        Symbol {
+            value: mem::transmute_copy(&address),
+            library: PhantomData,
+        }
+    }
+}
+
+impl<T> Deref for Symbol<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.value
+    }
+}
+
+impl<T> fmt::Debug for Symbol<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Symbol").finish_non_exhaustive()
+    }
+}
+
+/// A [`LibraryLoader`] for tests, handing out libraries whose symbols are functions and statics
+/// in the test binary itself, so the manager can be exercised without building any `.so` files.
+#[cfg(test)]
+pub(crate) mod mock {
+    use super::*;
+    use crate::plugin::CORE_ABI_VERSION;
+    use std::collections::HashMap;
+    use std::ffi::OsString;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    static ABI_VERSION: u32 = CORE_ABI_VERSION;
+
+    /// Knows a set of fake libraries by path, and counts how many of them are open.
+    #[derive(Default)]
+    pub(crate) struct MockLoader {
+        /// The symbols of each library, as addresses.
+        libraries: HashMap<OsString, HashMap<Vec<u8>, usize>>,
+        open: Arc<AtomicUsize>,
+    }
+
+    impl MockLoader {
+        /// Add a library at `path` exporting `symbols`, on top of a matching `_plugin_abi_version`
+        /// (which `symbols` can override).
+        pub(crate) fn library(mut self, path: &str, symbols: &[(&[u8], *const c_void)]) -> Self {
+            let mut exported = HashMap::new();
+            exported.insert(
+                b"_plugin_abi_version".to_vec(),
+                &ABI_VERSION as *const u32 as usize,
+            );
+            for (name, address) in symbols {
+                exported.insert(name.to_vec(), *address as usize);
+            }
+            self.libraries.insert(path.into(), exported);
+            self
+        }
+
+        /// How many of the libraries handed out haven't been dropped yet.
+        pub(crate) fn open_count(&self) -> Arc<AtomicUsize> {
+            Arc::clone(&self.open)
+        }
+    }
+
+    impl LibraryLoader for MockLoader {
+        unsafe fn load(&self, path: &OsStr) -> Result<Box<dyn LoadedLibrary>> {
+            let symbols = match self.libraries.get(path) {
+                Some(symbols) => symbols.clone(),
+                None => bail!(ErrorKind::LibraryLoadFailed(path.into())),
+            };
+            self.open.fetch_add(1, Ordering::SeqCst);
+            Ok(Box::new(MockLibrary {
+                symbols,
+                open: Arc::clone(&self.open),
+            }))
+        }
+    }
+
+    struct MockLibrary {
+        symbols: HashMap<Vec<u8>, usize>,
+        open: Arc<AtomicUsize>,
+    }
+
+    impl LoadedLibrary for MockLibrary {
+        unsafe fn symbol(&self, name: &[u8]) -> Result<*const c_void> {
+            match self.symbols.get(name) {
+                Some(&address) => Ok(address as *const c_void),
+                None => bail!(ErrorKind::SymbolNotFound(
+                    String::from_utf8_lossy(name).into_owned()
+                )),
+            }
+        }
+    }
+
+    impl Drop for MockLibrary {
+        fn drop(&mut self) {
+            self.open.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+}
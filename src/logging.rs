@@ -0,0 +1,98 @@
+use std::fmt;
+use std::sync::Arc;
+
+/// How important a message logged by the manager is, as passed to the callback set with
+/// [`PluginManager::set_logger`](crate::PluginManager::set_logger). These mirror the levels of
+/// the `log` crate, from the most to the least important.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LogLevel {
+    /// Something failed, e.g. a plugin panicked.
+    Error,
+    /// Something went wrong, but the manager carried on, e.g. a plugin failed to unload cleanly.
+    Warn,
+    /// Something the host would usually want to know about, e.g. what a plugin printed.
+    Info,
+    /// What the manager is doing, e.g. which plugins it is loading.
+    Debug,
+    /// The finer details of what the manager is doing, e.g. every file it skips.
+    Trace,
+}
+
+impl From<LogLevel> for log::Level {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Error => log::Level::Error,
+            LogLevel::Warn => log::Level::Warn,
+            LogLevel::Info => log::Level::Info,
+            LogLevel::Debug => log::Level::Debug,
+            LogLevel::Trace => log::Level::Trace,
+        }
+    }
+}
+
+/// Where a manager's messages go: the host's callback if it set one, otherwise the `log` facade.
+#[derive(Clone, Default)]
+pub(crate) struct Logger(Option<LogCallback>);
+
+/// A callback set with [`PluginManager::set_logger`](crate::PluginManager::set_logger).
+type LogCallback = Arc<dyn Fn(LogLevel, &str) + Send + Sync>;
+
+impl Logger {
+    pub(crate) fn new<F: Fn(LogLevel, &str) + Send + Sync + 'static>(callback: F) -> Self {
+        Logger(Some(Arc::new(callback)))
+    }
+
+    /// Log a message, under `target` if it goes to `log`.
+    pub(crate) fn log(&self, level: LogLevel, target: &str, args: fmt::Arguments<'_>) {
+        match &self.0 {
+            Some(callback) => callback(level, &args.to_string()),
+            None => log::log!(target: target, level.into(), "{}", args),
+        }
+    }
+}
+
+/// Log a message at `$level` through a [`Logger`], taking the same arguments as the `log` macros
+/// after the logger. The macros below do this for each level, and are used in place of `log`'s.
+macro_rules! log_at {
+    ($level:ident, $logger:expr, target: $target:expr, $($arg:tt)+) => {
+        $logger.log(
+            $crate::logging::LogLevel::$level,
+            $target,
+            format_args!($($arg)+),
+        )
+    };
+    ($level:ident, $logger:expr, $($arg:tt)+) => {
+        $logger.log(
+            $crate::logging::LogLevel::$level,
+            module_path!(),
+            format_args!($($arg)+),
+        )
+    };
+}
+pub(crate) use log_at;
+
+macro_rules! log_error {
+    ($($arg:tt)+) => { $crate::logging::log_at!(Error, $($arg)+) };
+}
+macro_rules! log_warn {
+    ($($arg:tt)+) => { $crate::logging::log_at!(Warn, $($arg)+) };
+}
+// Only captured output is logged at this level.
+#[cfg_attr(not(feature = "capture"), allow(unused_macros))]
+macro_rules! log_info {
+    ($($arg:tt)+) => { $crate::logging::log_at!(Info, $($arg)+) };
+}
+macro_rules! log_debug {
+    ($($arg:tt)+) => { $crate::logging::log_at!(Debug, $($arg)+) };
+}
+macro_rules! log_trace {
+    ($($arg:tt)+) => { $crate::logging::log_at!(Trace, $($arg)+) };
+}
+// `warn` can't be exported under its own name, which is taken by the built-in lint attribute, so
+// they are all renamed on the way out.
+pub(crate) use log_debug as debug;
+pub(crate) use log_error as error;
+#[cfg_attr(not(feature = "capture"), allow(unused_imports))]
+pub(crate) use log_info as info;
+pub(crate) use log_trace as trace;
+pub(crate) use log_warn as warn;
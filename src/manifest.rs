@@ -0,0 +1,127 @@
+use crate::errors::*;
+use crate::logging::debug;
+use crate::plugin_manager::{LoadReport, PluginId, PluginManager};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A plugin manifest, listing the plugins to load in the order they should be loaded in.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Manifest {
+    #[serde(default, rename = "plugin")]
+    plugins: Vec<ManifestEntry>,
+}
+
+/// A single `[[plugin]]` entry of a manifest.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ManifestEntry {
+    /// The name the plugin is expected to have.
+    name: String,
+    /// The plugin's library, relative to the folder the manifest is in.
+    path: PathBuf,
+    #[serde(default = "enabled_by_default")]
+    enabled: bool,
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+impl PluginManager {
+    /// Load the plugins listed in a TOML manifest, rather than everything found in a folder. Each
+    /// plugin is described by a `[[plugin]]` table:
+    ///
+    /// ```toml
+    /// [[plugin]]
+    /// name = "greeter"
+    /// path = "plugins/libgreeter.so"
+    ///
+    /// [[plugin]]
+    /// name = "exporter"
+    /// path = "/opt/app/plugins/libexporter.so"
+    /// enabled = false
+    /// ```
+    ///
+    /// Relative paths are resolved against the folder the manifest is in. Entries are loaded
+    /// strictly in the order they are listed, rather than in dependency order, so a plugin has to
+    /// be listed after the plugins it depends on. Entries with `enabled = false` are skipped.
+    ///
+    /// The library an entry points at has to provide a plugin with the entry's `name`, otherwise
+    /// it is rejected before any of its plugins' `on_plugin_load()` is fired. An entry whose file
+    /// doesn't exist fails with an error naming the entry. As with
+    /// [`PluginManager::load_plugins`], every entry is attempted even if an earlier one fails, and
+    /// the returned [`LoadReport`] records which files loaded and which failed.
+    ///
+    /// An error is returned if the manifest itself can't be read or isn't valid, in which case
+    /// nothing is loaded.
+    ///
+    /// # Safety
+    ///
+    /// Every entry is loaded as if by [`PluginManager::load_plugin`], so the same requirements
+    /// apply to each library listed in the manifest.
+    pub unsafe fn load_from_manifest<P: AsRef<Path>>(
+        &mut self,
+        manifest_path: P,
+    ) -> Result<LoadReport> {
+        let manifest_path = manifest_path.as_ref();
+        let contents = fs::read_to_string(manifest_path)
+            .chain_err(|| ErrorKind::ManifestInvalid(manifest_path.into()))?;
+        let manifest: Manifest = toml::from_str(&contents)
+            .chain_err(|| ErrorKind::ManifestInvalid(manifest_path.into()))?;
+        let base = manifest_path.parent().unwrap_or(Path::new(""));
+
+        let mut report = LoadReport::default();
+        for entry in manifest.plugins {
+            let path = base.join(&entry.path);
+            if !entry.enabled {
+                debug!(
+                    self.logger,
+                    "Skipping `{}` ({:?}), since it is disabled", entry.name, path
+                );
+                continue;
+            }
+
+            match self.load_entry(&entry, &path) {
+                Ok(ids) => {
+                    report.plugins.extend(self.names_of(&ids));
+                    report.loaded.push(path);
+                }
+                Err(e) => {
+                    debug!(
+                        self.logger,
+                        "Failed to load `{}` from {:?}: {}", entry.name, path, e
+                    );
+                    report.failed.push((path, e));
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Load the library of a manifest entry, checking it provides the plugin the entry names.
+    unsafe fn load_entry(&mut self, entry: &ManifestEntry, path: &Path) -> Result<Vec<PluginId>> {
+        if !path.is_file() {
+            bail!(ErrorKind::ManifestEntryMissing(
+                entry.name.clone(),
+                path.into()
+            ));
+        }
+
+        if let Some(ids) = self.check_not_loaded(path.as_os_str())? {
+            return Ok(ids);
+        }
+
+        let candidate = self.prepare(path.as_os_str())?;
+        if !candidate.plugins.iter().any(|p| p.name() == entry.name) {
+            bail!(ErrorKind::ManifestEntryMismatch(
+                entry.name.clone(),
+                path.into()
+            ));
+        }
+
+        self.activate(candidate)
+    }
+}
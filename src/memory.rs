@@ -0,0 +1,119 @@
+use crate::errors::*;
+use crate::loader::LoadedLibrary;
+use crate::plugin_manager::PLUGIN_EXTENSION;
+use std::ffi::c_void;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::{env, process};
+
+/// What plugins loaded from memory report as the path they were loaded from.
+pub(crate) const IN_MEMORY_PATH: &str = "<memory>";
+
+/// A copy of a plugin library's contents, put somewhere the dynamic linker can open it from.
+///
+/// On Linux this is an anonymous `memfd`, which never touches the filesystem. Everywhere else (or
+/// if the kernel doesn't support `memfd_create`) it is a temporary file only the current user can
+/// read, which is deleted again as soon as the platform allows.
+pub(crate) struct LibraryImage {
+    path: PathBuf,
+    /// The `memfd` the path refers to. This is kept open for as long as the library is loaded,
+    /// since the dynamic linker recognises libraries by their path, and would mistake another
+    /// image reusing the same file descriptor for this one.
+    memfd: Option<File>,
+}
+
+impl LibraryImage {
+    pub(crate) fn new(bytes: &[u8]) -> Result<Self> {
+        let failed = || ErrorKind::LibraryLoadFailed(IN_MEMORY_PATH.into());
+
+        #[cfg(target_os = "linux")]
+        if let Some(mut memfd) = memfd() {
+            memfd.write_all(bytes).chain_err(failed)?;
+            let path = format!("/proc/self/fd/{}", std::os::fd::AsRawFd::as_raw_fd(&memfd));
+            return Ok(LibraryImage {
+                path: path.into(),
+                memfd: Some(memfd),
+            });
+        }
+
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let name = format!(
+            "plugin-{}-{}.{}",
+            process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed),
+            PLUGIN_EXTENSION
+        );
+        let path = env::temp_dir().join(name);
+
+        let mut options = OpenOptions::new();
+        // Refuse to reuse an existing file, which could have been planted there by someone else.
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o700);
+
+        let image = LibraryImage { path, memfd: None };
+        let mut file = options.open(&image.path).chain_err(failed)?;
+        file.write_all(bytes).chain_err(failed)?;
+        Ok(image)
+    }
+
+    /// Where the library can be opened from.
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Clean up once `library` has been opened from the image, returning the library to use in
+    /// its place.
+    pub(crate) fn opened(self, library: Box<dyn LoadedLibrary>) -> Box<dyn LoadedLibrary> {
+        // Unix lets a file be deleted while it is mapped, but Windows doesn't, so there the file
+        // is only deleted once the library has been dropped.
+        if self.memfd.is_none() && cfg!(unix) {
+            return library;
+        }
+        Box::new(ImageLibrary {
+            library,
+            _image: self,
+        })
+    }
+}
+
+impl Drop for LibraryImage {
+    fn drop(&mut self) {
+        if self.memfd.is_none() {
+            let _ = fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// A library opened from an image which has to stay around until the library is dropped.
+struct ImageLibrary {
+    // Declared before the image, so that the library is dropped before its file goes away.
+    library: Box<dyn LoadedLibrary>,
+    _image: LibraryImage,
+}
+
+impl LoadedLibrary for ImageLibrary {
+    unsafe fn symbol(&self, name: &[u8]) -> Result<*const c_void> {
+        self.library.symbol(name)
+    }
+}
+
+/// Create an anonymous in-memory file, if the kernel supports it.
+#[cfg(target_os = "linux")]
+fn memfd() -> Option<File> {
+    use std::os::fd::FromRawFd;
+    use std::os::raw::{c_char, c_int, c_uint};
+
+    extern "C" {
+        fn memfd_create(name: *const c_char, flags: c_uint) -> c_int;
+    }
+    const MFD_CLOEXEC: c_uint = 1;
+
+    let fd = unsafe { memfd_create(c"plugin".as_ptr(), MFD_CLOEXEC) };
+    if fd < 0 {
+        return None;
+    }
+    Some(unsafe { File::from_raw_fd(fd) })
+}
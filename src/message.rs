@@ -0,0 +1,65 @@
+use crate::plugin_manager::PluginId;
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+/// A message sent to every loaded plugin through
+/// [`PluginManager::send_message`](crate::PluginManager::send_message), so plugins can coordinate
+/// without knowing each other's types.
+///
+/// Unlike a [`PluginEvent`](crate::PluginEvent), a message's payload is plain bytes rather than a
+/// Rust value, so it can be understood by plugins built separately from whoever sent it, as long
+/// as they agree on its encoding for the `topic`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PluginMessage {
+    pub topic: String,
+    pub payload: Vec<u8>,
+}
+
+impl PluginMessage {
+    /// Create a message with the given topic, carrying `payload`.
+    pub fn new<S: Into<String>, P: Into<Vec<u8>>>(topic: S, payload: P) -> Self {
+        Self {
+            topic: topic.into(),
+            payload: payload.into(),
+        }
+    }
+}
+
+/// Messages sent by plugins which haven't been delivered yet, along with who sent each.
+pub(crate) type Outbox = Arc<Mutex<VecDeque<(Option<PluginId>, PluginMessage)>>>;
+
+/// Lets a plugin send messages to the other plugins by itself, rather than only replying to the
+/// host. Get one from [`PluginContext::message_sender`](crate::PluginContext::message_sender)
+/// while loading, and keep it for as long as the plugin wants to send messages.
+///
+/// Sending only queues the message. The manager delivers it to every enabled plugin but the one
+/// which sent it the next time it is told to deliver messages (see
+/// [`PluginManager::deliver_messages`](crate::PluginManager::deliver_messages)).
+#[derive(Clone)]
+pub struct MessageSender {
+    owner: Option<PluginId>,
+    outbox: Outbox,
+}
+
+impl MessageSender {
+    pub(crate) fn new(owner: Option<PluginId>, outbox: Outbox) -> Self {
+        Self { owner, outbox }
+    }
+
+    /// Queue `msg` to be delivered to the other plugins.
+    pub fn send(&self, msg: PluginMessage) {
+        self.outbox
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push_back((self.owner, msg));
+    }
+}
+
+impl fmt::Debug for MessageSender {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MessageSender")
+            .field("owner", &self.owner)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,15 @@
+/// A description of a plugin, for hosts which want to show more than just its name (e.g. in a
+/// table of loaded plugins).
+///
+/// Everything in here is owned, so it can be kept around (or sent elsewhere) independently of the
+/// plugin it describes.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PluginMetadata {
+    pub name: String,
+    pub version: String,
+    pub author: String,
+    pub description: String,
+    /// Free-form labels for the functionality the plugin provides, e.g. `"exporter"`.
+    pub capabilities: Vec<String>,
+}
@@ -0,0 +1,191 @@
+use crate::logging::{error, Logger};
+use crate::plugin_manager::log_target;
+use std::cell::RefCell;
+use std::panic::{self, AssertUnwindSafe, PanicHookInfo};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+type Hook = Box<dyn Fn(&PanicHookInfo<'_>) + Send + Sync + 'static>;
+
+thread_local! {
+    /// The plugins currently being called on this thread, innermost last, along with where the
+    /// manager calling them logs to.
+    static CALLING: RefCell<Vec<(String, Logger)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Our panic hook, while it is installed.
+static INSTALLED: Mutex<Option<Installed>> = Mutex::new(None);
+
+struct Installed {
+    /// How many plugin calls (on any thread) are relying on the hook.
+    calls: usize,
+    /// The hook which was in place before ours, to be put back once the last call returns.
+    previous: Arc<Hook>,
+    /// The address of our hook, to tell whether it is still the one in place.
+    ours: usize,
+}
+
+/// Run `f`, a call into `plugin`, catching any panic like [`panic::catch_unwind`].
+///
+/// For the duration of the call a panic hook is installed which logs the name of the plugin that
+/// panicked, and where, under the plugin's log target, before handing over to whichever hook was
+/// there before. That hook is put back once no plugin is being called any more, unless the host
+/// has installed another one in the meantime, which is then left alone.
+pub(crate) fn catch_panic<R>(
+    plugin: &str,
+    logger: &Logger,
+    f: impl FnOnce() -> R,
+) -> thread::Result<R> {
+    let _scope = Scope::enter(plugin, logger);
+    panic::catch_unwind(AssertUnwindSafe(f))
+}
+
+/// A plugin call which the panic hook is tagging. The hook is removed again once every scope is
+/// dropped.
+struct Scope;
+
+impl Scope {
+    fn enter(plugin: &str, logger: &Logger) -> Option<Scope> {
+        // Taking or setting the hook while unwinding would panic again, e.g. when plugins are
+        // unloaded by a manager dropped during a panic, so the call just isn't tagged.
+        if thread::panicking() {
+            return None;
+        }
+
+        let mut installed = INSTALLED.lock().unwrap_or_else(|e| e.into_inner());
+        match installed.as_mut() {
+            Some(installed) => installed.calls += 1,
+            None => {
+                let previous = Arc::new(panic::take_hook());
+                let delegate = Arc::clone(&previous);
+                let hook: Hook = Box::new(move |info| {
+                    tag(info);
+                    delegate(info);
+                });
+                let ours = address(&hook);
+                panic::set_hook(hook);
+                *installed = Some(Installed {
+                    calls: 1,
+                    previous,
+                    ours,
+                });
+            }
+        }
+        drop(installed);
+
+        CALLING.with(|calling| {
+            calling
+                .borrow_mut()
+                .push((plugin.to_owned(), logger.clone()))
+        });
+        Some(Scope)
+    }
+}
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        let _ = CALLING.try_with(|calling| calling.borrow_mut().pop());
+
+        let mut guard = INSTALLED.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(installed) = guard.as_mut() else {
+            return;
+        };
+        installed.calls -= 1;
+        if installed.calls > 0 || thread::panicking() {
+            return;
+        }
+
+        let Installed { previous, ours, .. } = guard.take().unwrap();
+        let current = panic::take_hook();
+        if address(&current) != ours {
+            // The host replaced our hook while a plugin was running, so theirs takes precedence.
+            panic::set_hook(current);
+            return;
+        }
+
+        // Dropping our hook leaves the previous one with no other owner.
+        drop(current);
+        match Arc::try_unwrap(previous) {
+            Ok(previous) => panic::set_hook(previous),
+            Err(previous) => panic::set_hook(Box::new(move |info| previous(info))),
+        }
+    }
+}
+
+/// Log which plugin the current thread was calling when it panicked, if any.
+fn tag(info: &PanicHookInfo<'_>) {
+    let _ = CALLING.try_with(|calling| {
+        let Ok(calling) = calling.try_borrow() else {
+            return;
+        };
+        if let Some((plugin, logger)) = calling.last() {
+            match info.location() {
+                Some(location) => error!(
+                    logger,
+                    target: &log_target(plugin),
+                    "The plugin `{}` panicked at {}", plugin, location
+                ),
+                None => {
+                    error!(logger, target: &log_target(plugin), "The plugin `{}` panicked", plugin)
+                }
+            }
+        }
+    });
+}
+
+fn address(hook: &Hook) -> usize {
+    &**hook as *const _ as *const () as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logging::LogLevel;
+
+    fn calling() -> Vec<String> {
+        CALLING.with(|calling| {
+            calling
+                .borrow()
+                .iter()
+                .map(|(name, _)| name.clone())
+                .collect()
+        })
+    }
+
+    #[test]
+    fn nested_calls_are_tracked_innermost_last() {
+        let logger = Logger::default();
+        let seen = catch_panic("outer", &logger, || {
+            catch_panic("inner", &logger, calling).unwrap()
+        })
+        .unwrap();
+        assert_eq!(seen, ["outer", "inner"]);
+        assert!(calling().is_empty());
+    }
+
+    #[test]
+    fn a_panic_is_caught_and_its_call_forgotten() {
+        let caught = catch_panic("panicky", &Logger::default(), || panic!("boom")).unwrap_err();
+        assert_eq!(caught.downcast_ref::<&str>(), Some(&"boom"));
+        assert!(calling().is_empty());
+    }
+
+    #[test]
+    fn a_panic_is_tagged_through_the_managers_logger() {
+        let logged = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&logged);
+        let logger = Logger::new(move |level, message: &str| {
+            sink.lock().unwrap().push((level, message.to_owned()))
+        });
+
+        let _ = catch_panic("panicky", &logger, || panic!("boom"));
+        let logged = logged.lock().unwrap();
+        assert_eq!(logged.len(), 1);
+        assert_eq!(logged[0].0, LogLevel::Error);
+        assert!(
+            logged[0].1.starts_with("The plugin `panicky` panicked at "),
+            "{}",
+            logged[0].1
+        );
+    }
+}
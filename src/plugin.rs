@@ -1,35 +1,457 @@
+use crate::command::CommandSpec;
+use crate::context::PluginContext;
+use crate::errors::*;
+use crate::event::PluginEvent;
+use crate::health::HealthStatus;
+use crate::message::PluginMessage;
+use crate::metadata::PluginMetadata;
 use std::any::Any;
+use std::ffi::c_void;
+use std::time::Duration;
+
+/// The version of the binary interface between a host and its plugins. This is bumped whenever a
+/// change to this crate would make a plugin built against an older version unsafe to load.
+///
+/// Every plugin library has to export it as a `_plugin_abi_version` static, which
+/// [`declare_plugin!`](crate::declare_plugin) does automatically.
+pub const CORE_ABI_VERSION: u32 = 2;
+
+/// The version of the plugin API (the [`Plugin`] trait and everything it hands plugins) this crate
+/// provides, following semver: the major version is bumped by changes which break existing
+/// plugins, and the minor version by additions which only newer plugins rely on.
+///
+/// Every plugin library built with one of the `declare_*` macros exports the version it was built
+/// against as a `_plugin_api_semver` static of type `&str`. With the `semver` feature enabled, the
+/// manager only loads a plugin if its version has the same major version as this one, and a
+/// minor version no greater than this one's.
+pub const PLUGIN_API_VERSION: &str = "1.0.0";
 
 /// A plugin which allows you to add extra functionality to any conforming application
+///
+/// Only [`name`](Plugin::name) has to be implemented. Every other method has a default which does
+/// nothing (or reports nothing), so a plugin only overrides the callbacks it cares about.
+///
+/// # Thread Safety
+///
+/// Plugins are required to be `Send + Sync`, so that a [`PluginManager`](crate::PluginManager)
+/// can be loaded once and then shared between threads (e.g. in an `Arc<RwLock<_>>`). This means
+/// a plugin may have its methods called from any thread, and methods taking `&self` may be called
+/// from several threads at once, so any interior mutability in the plugin has to go through
+/// thread-safe types like `Mutex` or the atomics rather than `Cell` or `RefCell`.
 pub trait Plugin: Any + Send + Sync {
     /// Get a name describing the `Plugin`
+    ///
+    /// The manager copies the name when the plugin is loaded, and goes by that copy from then on
+    /// (for lookups, logging and error messages), so it should stay the same for as long as the
+    /// plugin is loaded. Despite the `'static`, a name compiled into a plugin's library only lives
+    /// as long as the library does, which is why it isn't kept around as a reference.
     fn name(&self) -> &'static str;
 
+    /// Get the version of the `Plugin`, used for diagnostics.
+    fn version(&self) -> &str {
+        "unknown"
+    }
+
+    /// Describe the plugin. By default this only fills in the name and version, leaving
+    /// everything else empty.
+    fn metadata(&self) -> PluginMetadata {
+        PluginMetadata {
+            name: self.name().to_owned(),
+            version: self.version().to_owned(),
+            ..PluginMetadata::default()
+        }
+    }
+
+    /// The names of the plugins this plugin depends on. When loading a folder of plugins, every
+    /// dependency found in the folder has its `on_plugin_load()` fired before this plugin's. A
+    /// plugin is only loaded once all of its dependencies are.
+    fn dependencies(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// How late the plugin should be unloaded by [`PluginManager::unload`](crate::PluginManager::unload),
+    /// relative to the other plugins: the higher the priority, the later it is unloaded. This lets
+    /// a plugin the others rely on without declaring it as a dependency (such as one which logs
+    /// their shutdown messages) stay around until everyone else is gone. Defaults to `0`.
+    ///
+    /// Dependencies still come first: a plugin is always unloaded before the plugins it depends
+    /// on, whatever its priority.
+    fn shutdown_priority(&self) -> i32 {
+        0
+    }
+
     /// A callback fired immediately after the plugin is loaded. Usually used for initialization.
-    fn on_plugin_load(&self) {}
+    ///
+    /// The `PluginContext` gives access to the host's services, and lets the plugin register
+    /// services of its own.
+    ///
+    /// Returning an error marks the load as failed, and the plugin is discarded without being
+    /// registered with the host.
+    fn on_plugin_load(&self, _ctx: &mut PluginContext) -> Result<()> {
+        Ok(())
+    }
+
+    /// A callback fired once per tick of the host's main loop, with the time elapsed since the
+    /// previous tick.
+    fn update(&mut self, _delta: Duration) {}
+
+    /// The topics of the events the plugin wants [`on_event`](Plugin::on_event) to be fired for.
+    /// This is asked once, when the plugin is loaded, and the manager skips the plugin for events
+    /// with any other topic. Defaults to an empty list, meaning every event.
+    fn subscriptions(&self) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// A callback fired whenever the host broadcasts an event, unless the plugin only
+    /// [subscribed](Plugin::subscriptions) to other topics. Plugins which aren't interested in a
+    /// particular event can simply ignore it.
+    fn on_event(&mut self, _event: &PluginEvent) {}
+
+    /// A callback fired for every message sent through
+    /// [`PluginManager::send_message`](crate::PluginManager::send_message) by the host, or through
+    /// a [`MessageSender`](crate::MessageSender) by another plugin. Plugins which aren't interested
+    /// in a particular topic can simply ignore it.
+    fn receive_message(&mut self, _msg: &PluginMessage) {}
+
+    /// The commands the plugin handles for the host, e.g. subcommands of its CLI, which are run
+    /// through [`run_command`](Plugin::run_command). This is asked once, when the plugin is loaded.
+    /// By default plugins have no commands.
+    fn commands(&self) -> Vec<CommandSpec> {
+        Vec::new()
+    }
+
+    /// Run one of the plugin's [commands](Plugin::commands), as requested through
+    /// [`PluginManager::run_command`](crate::PluginManager::run_command), returning its exit code.
+    /// This is only called with the names the plugin listed. By default it fails with an
+    /// `UnknownCommand` error.
+    fn run_command(&mut self, name: &str, _args: &[String]) -> Result<i32> {
+        bail!(ErrorKind::UnknownCommand(name.to_owned()))
+    }
+
+    /// Report whether the plugin is working properly, e.g. for a host exposing a health endpoint
+    /// through [`PluginManager::health`](crate::PluginManager::health). This may be called at any
+    /// time from any thread, so it should be quick. By default plugins always report
+    /// [`HealthStatus::Ok`].
+    fn health_check(&self) -> HealthStatus {
+        HealthStatus::Ok
+    }
+
+    /// Capture the plugin's in-memory state before it is replaced by
+    /// [`PluginManager::reload_plugin`](crate::PluginManager::reload_plugin), so the new instance
+    /// can pick up where this one left off. This is called before `on_plugin_unload()`. By default
+    /// plugins have no state to carry over.
+    ///
+    /// The new instance may come from a rebuilt library, so the state should be in a format
+    /// which stays readable across versions of the plugin.
+    fn save_state(&self) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// Take over the state captured by [`save_state`](Plugin::save_state) from the plugin with
+    /// the same name being replaced. This is called on the freshly constructed instance, before
+    /// its `on_plugin_load()` is fired. By default the state is ignored.
+    fn restore_state(&mut self, _state: &[u8]) {}
 
     /// A callback fired immediately before the plugin is unloaded. Use this if you need to do
     /// any cleanup.
-    fn on_plugin_unload(&self) {}
+    ///
+    /// The plugin is unloaded regardless, but any error returned is reported back to the host.
+    fn on_plugin_unload(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl dyn Plugin {
+    /// View the plugin as [`Any`], so it can be downcast to its concrete type. Every plugin gets
+    /// this for free, since `Plugin` requires `Any`.
+    ///
+    /// Downcasting only succeeds if the host and the plugin agree on the type, which is
+    /// guaranteed for plugins compiled into the host itself, but not for ones loaded from a
+    /// library built separately.
+    pub fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    /// View the plugin as [`Any`] mutably, like [`as_any`](#method.as_any).
+    pub fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
 }
 
 /// Declare a plugin type and its constructor.
 ///
 /// # Notes
 ///
-/// This works by automatically generating an `extern "C"` function with a pre-defined signature and
-/// symbol name. Therefore you will only be able to declare one plugin per library.
+/// This works by automatically generating a `_plugin_create` function with a pre-defined signature
+/// and symbol name. Therefore you will only be able to declare one plugin per library; use
+/// [`declare_plugins!`](crate::declare_plugins) to export several.
+///
+/// It also exports the `_plugin_abi_version` static the host checks before constructing the plugin,
+/// and the `_plugin_api_semver` static described by
+/// [`PLUGIN_API_VERSION`](crate::PLUGIN_API_VERSION).
+///
+/// The plugin is boxed and leaked by `_plugin_create`, and ownership of the allocation passes to
+/// the [`PluginManager`](crate::PluginManager), which reclaims it with `Box::from_raw`. Writing
+/// these symbols by hand is possible, but easy to get subtly wrong.
+///
+/// # The ABI Contract
+///
+/// The host expects a library to export these symbols, which is what this macro expands to:
+///
+/// ```
+/// use plugin_framework::{Plugin, CORE_ABI_VERSION};
+///
+/// pub struct Greeter;
+///
+/// impl Plugin for Greeter {
+///     fn name(&self) -> &'static str {
+///         "greeter"
+///     }
+/// }
+///
+/// #[no_mangle]
+/// #[allow(non_upper_case_globals)]
+/// pub static _plugin_abi_version: u32 = CORE_ABI_VERSION;
+///
+/// #[no_mangle]
+/// #[allow(improper_ctypes_definitions)]
+/// pub extern "C-unwind" fn _plugin_create() -> *mut dyn Plugin {
+///     Box::into_raw(Box::new(Greeter))
+/// }
+/// ```
+///
+/// The constructor returns a pointer obtained from `Box::into_raw`, which the host takes ownership
+/// of. A null pointer is rejected with a `ConstructorReturnedNull` error (except from a
+/// `_plugin_create_v2` constructor, where it means the plugin declined the host), and the library
+/// dropped again without anything being dereferenced. It uses the C calling convention so the
+/// call itself doesn't depend on how a particular compiler lays out Rust calls.
+///
+/// The convention is `C-unwind` rather than plain `C` so that a panic in the constructor is
+/// defined behaviour instead of an abort on the spot. If the plugin shares the host's copy of the
+/// standard library (e.g. its constructor is linked into the host, or both are built with
+/// `-C prefer-dynamic` by the same toolchain), the panic is caught and returned as a
+/// `ConstructorPanicked` error. A `cdylib` has a copy of its own, whose panics the host can't
+/// catch, so they still abort the process, but with a message saying why.
+///
+/// The calling convention is all this pins down. The plugin is still handed over as a `dyn Plugin`,
+/// whose vtable layout isn't stable either, so plugins have to be built with the same compiler
+/// (and the same version of this crate) as the host.
+///
+/// # Example
+///
+/// In a library built with `crate-type = ["cdylib"]`:
+///
+/// ```
+/// use plugin_framework::{declare_plugin, Plugin};
+///
+/// #[derive(Default)]
+/// pub struct Greeter;
+///
+/// impl Plugin for Greeter {
+///     fn name(&self) -> &'static str {
+///         "greeter"
+///     }
+/// }
+///
+/// declare_plugin!(Greeter, Greeter::default);
+/// ```
 #[macro_export]
 macro_rules! declare_plugin {
-    ($plugin_type: ty, $constructor: path) => {
+    ($plugin_type: ty, $constructor: expr) => {
+        #[no_mangle]
+        #[allow(non_upper_case_globals)]
+        pub static _plugin_abi_version: u32 = $crate::CORE_ABI_VERSION;
+
         #[no_mangle]
-        pub extern "C" fn _plugin_create() -> *mut $crate::Plugin {
+        #[allow(non_upper_case_globals)]
+        pub static _plugin_api_semver: &str = $crate::PLUGIN_API_VERSION;
+
+        #[no_mangle]
+        #[allow(improper_ctypes_definitions)]
+        pub extern "C-unwind" fn _plugin_create() -> *mut dyn $crate::Plugin {
             // make sure the constructor is the correct type.
             let constructor: fn() -> $plugin_type = $constructor;
 
             let object = constructor();
-            let boxed: Box<$crate::Plugin> = Box::new(object);
+            let boxed: Box<dyn $crate::Plugin> = Box::new(object);
             Box::into_raw(boxed)
         }
     };
 }
+
+/// Declare a plugin type and a constructor which is told the version of the host, so the plugin
+/// can decline to load into a host it doesn't support.
+///
+/// # Notes
+///
+/// This works like [`declare_plugin!`](crate::declare_plugin), but generates a
+/// `_plugin_create_v2` function instead, which is called with the host's version (as set with
+/// [`PluginManagerBuilder::host_version`](crate::PluginManagerBuilder::host_version)). The
+/// constructor returns `None` to decline, in which case the host gets an
+/// [`ErrorKind::IncompatibleHost`] error and drops the library without registering anything.
+///
+/// The generated function follows the same [ABI contract](crate::declare_plugin#the-abi-contract)
+/// as `_plugin_create`, with the signature `extern "C-unwind" fn(u32) -> *mut dyn Plugin`. A
+/// declined load is returned as a null pointer.
+///
+/// What the version means is up to the host, which should document which versions offer what.
+/// The check happens on top of `_plugin_abi_version`, which still has to match first.
+///
+/// # Example
+///
+/// ```
+/// use plugin_framework::{declare_versioned_plugin, Plugin};
+///
+/// pub struct Greeter;
+///
+/// impl Greeter {
+///     fn for_host(host_version: u32) -> Option<Greeter> {
+///         // Greetings were only added to the host in version 2.
+///         (host_version >= 2).then_some(Greeter)
+///     }
+/// }
+///
+/// impl Plugin for Greeter {
+///     fn name(&self) -> &'static str {
+///         "greeter"
+///     }
+/// }
+///
+/// declare_versioned_plugin!(Greeter, Greeter::for_host);
+/// ```
+#[macro_export]
+macro_rules! declare_versioned_plugin {
+    ($plugin_type: ty, $constructor: expr) => {
+        #[no_mangle]
+        #[allow(non_upper_case_globals)]
+        pub static _plugin_abi_version: u32 = $crate::CORE_ABI_VERSION;
+
+        #[no_mangle]
+        #[allow(non_upper_case_globals)]
+        pub static _plugin_api_semver: &str = $crate::PLUGIN_API_VERSION;
+
+        #[no_mangle]
+        #[allow(improper_ctypes_definitions)]
+        pub extern "C-unwind" fn _plugin_create_v2(host_version: u32) -> *mut dyn $crate::Plugin {
+            // make sure the constructor is the correct type.
+            let constructor: fn(u32) -> Option<$plugin_type> = $constructor;
+
+            match constructor(host_version) {
+                Some(object) => {
+                    let boxed: Box<dyn $crate::Plugin> = Box::new(object);
+                    Box::into_raw(boxed)
+                }
+                // Any type will do for a null pointer, it just needs a vtable to become a `dyn`.
+                None => ::std::ptr::null_mut::<$plugin_type>() as *mut dyn $crate::Plugin,
+            }
+        }
+    };
+}
+
+/// The callback a library's `_plugin_create_all` constructor hands each of its plugins to, along
+/// with the `registry` pointer it was given. See [`declare_plugins!`](crate::declare_plugins).
+#[allow(improper_ctypes_definitions)]
+pub type PluginRegistrar =
+    unsafe extern "C-unwind" fn(registry: *mut c_void, plugin: *mut dyn Plugin);
+
+/// Declare several plugin types, and their constructors, to be exported from the same library.
+///
+/// # Notes
+///
+/// This works like [`declare_plugin!`](crate::declare_plugin), but generates a
+/// `_plugin_create_all` function instead, returning every plugin in the order they are declared.
+/// The [`PluginManager`](crate::PluginManager) loads them all from the one library, which stays
+/// loaded until the last of them is unloaded.
+///
+/// The generated function follows the same [ABI contract](crate::declare_plugin#the-abi-contract)
+/// as `_plugin_create`, but rather than returning a collection (which has no C equivalent), it
+/// hands each plugin back to the host through a [`PluginRegistrar`] callback:
+///
+/// ```text
+/// #[no_mangle]
+/// pub extern "C-unwind" fn _plugin_create_all(register: PluginRegistrar, registry: *mut c_void)
+/// ```
+///
+/// calling `register(registry, plugin)` once per plugin, with a pointer obtained from
+/// `Box::into_raw`, in the order they should be loaded. `registry` is the host's, and is only
+/// valid for the duration of the call.
+///
+/// # Example
+///
+/// ```
+/// use plugin_framework::{declare_plugins, Plugin};
+///
+/// #[derive(Default)]
+/// pub struct Greeter;
+///
+/// impl Plugin for Greeter {
+///     fn name(&self) -> &'static str {
+///         "greeter"
+///     }
+/// }
+///
+/// #[derive(Default)]
+/// pub struct Farewell;
+///
+/// impl Plugin for Farewell {
+///     fn name(&self) -> &'static str {
+///         "farewell"
+///     }
+/// }
+///
+/// declare_plugins!((Greeter, Greeter::default), (Farewell, Farewell::default));
+/// ```
+#[macro_export]
+macro_rules! declare_plugins {
+    ($(($plugin_type: ty, $constructor: expr)),+ $(,)?) => {
+        #[no_mangle]
+        #[allow(non_upper_case_globals)]
+        pub static _plugin_abi_version: u32 = $crate::CORE_ABI_VERSION;
+
+        #[no_mangle]
+        #[allow(non_upper_case_globals)]
+        pub static _plugin_api_semver: &str = $crate::PLUGIN_API_VERSION;
+
+        #[no_mangle]
+        #[allow(improper_ctypes_definitions)]
+        pub extern "C-unwind" fn _plugin_create_all(
+            register: $crate::PluginRegistrar,
+            registry: *mut ::std::ffi::c_void,
+        ) {
+            $({
+                // make sure the constructor is the correct type.
+                let constructor: fn() -> $plugin_type = $constructor;
+
+                let boxed: Box<dyn $crate::Plugin> = Box::new(constructor());
+                unsafe { register(registry, Box::into_raw(boxed)) };
+            })+
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Minimal;
+
+    impl Plugin for Minimal {
+        fn name(&self) -> &'static str {
+            "minimal"
+        }
+    }
+
+    #[test]
+    fn a_plugin_only_needs_a_name() {
+        let mut plugin = Minimal;
+        let mut context = PluginContext::new();
+        plugin.on_plugin_load(&mut context).unwrap();
+        plugin.update(Duration::ZERO);
+        assert_eq!(plugin.version(), "unknown");
+        assert!(plugin.dependencies().is_empty());
+        assert!(plugin.save_state().is_none());
+        assert_eq!(plugin.shutdown_priority(), 0);
+        assert_eq!(plugin.metadata().name, "minimal");
+        plugin.on_plugin_unload().unwrap();
+    }
+}
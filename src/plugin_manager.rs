@@ -1,17 +1,42 @@
+use crate::command::CommandSpec;
+use crate::config::PluginConfig;
+use crate::context::PluginContext;
 use crate::errors::*;
-use crate::plugin::Plugin;
-use libloading::{Library, Symbol};
-use log::{debug, trace};
-use std::ffi::OsStr;
+use crate::event::PluginEvent;
+use crate::health::HealthStatus;
+use crate::limits::ResourceLimits;
+use crate::loader::{LibraryLoader, LoadedLibrary, NativeLoader, Symbol};
+use crate::logging::{debug, error, trace, warn, LogLevel, Logger};
+use crate::memory::{LibraryImage, IN_MEMORY_PATH};
+use crate::message::PluginMessage;
+use crate::metadata::PluginMetadata;
+use crate::panic_hook::catch_panic;
+use crate::plugin::{Plugin, PluginRegistrar, CORE_ABI_VERSION};
+use crate::snapshot::PluginSnapshot;
+use crate::timings::PluginTimings;
+use std::any::Any;
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::ffi::{c_void, OsStr};
+use std::fmt;
+use std::fs;
+use std::mem;
+use std::num::NonZeroUsize;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
 
 /// This structure manages all the plugins that are loaded, and calls the appropriate functions at
 /// the appropriate time, while also keeping track of their lifetimes.
 ///
 /// # Note
 ///
-/// Something we need to keep in mind is that any `Library` we load will need to outlive our plugins.
+/// Something we need to keep in mind is that any library we load will need to outlive our plugins.
 /// This is because they contain the code for executing the various `Plugin` methods, so if the
-/// `Library` is dropped too early our plugins' vtable could end up pointing at garbage... Which would be bad.
+/// library is dropped too early our plugins' vtable could end up pointing at garbage... Which would be bad.
 ///
 /// # Note on Destroy
 ///
@@ -21,79 +46,5304 @@ use std::ffi::OsStr;
 /// `unload()` method also panics will result in a second panic. This usually results in aborting the
 /// entire program because your program is most probably FUBAR.
 ///
+/// To avoid that, dropping the manager catches any panic coming out of the plugins while they are
+/// unloaded, whatever its [`PanicPolicy`], and logs it instead.
+///
 /// # Note on Customization
 ///
-/// This is a bare minimum plugin manager, with just the capability to load and unload plugins. This is
-/// the reason why the members are made public. For any application wishing to support plugins, it'd
-/// have to extend this PluginManager with it's own, possibly calling additional functions on all the
-/// plugins, or rejecting a plugin library if it doesn't contain the expected set of functions beyond
-/// the ones defined in the `Plugin` trait provided with this library.
+/// This is a bare minimum plugin manager, with just the capability to load and unload plugins. For
+/// any application wishing to support plugins, it'd have to extend this PluginManager with it's own,
+/// possibly calling additional functions on all the plugins (see [`PluginManager::plugins_mut`] and
+/// [`PluginManager::get_symbol`]), or rejecting a plugin library if it doesn't contain the expected
+/// set of functions beyond the ones defined in the `Plugin` trait provided with this library (see
+/// [`PluginManager::load_plugin_requiring`] and [`PluginManager::register_hook`]).
 /// An example of this can be seen in the [rust_ffi_example repo](https://github.com/Srikrishna31/rust_ffi_example)
+///
+/// The plugins and their libraries are private, and only handed out by reference, since the
+/// manager relies on every library outliving the plugins created from it, and keeps bookkeeping
+/// alongside them (such as which library each plugin came from).
+///
+/// # Note on Logging
+///
+/// Messages about a particular plugin (loading, unloading, panics) are logged under the target
+/// `plugin::<name>`, so a single plugin can be singled out with e.g.
+/// `RUST_LOG=plugin::greeter=trace`. Anything else the manager logs uses this crate's module as
+/// usual. This only covers what the manager itself logs; plugins' own log calls use whatever
+/// target they choose. A host without a `log` logger can have the messages handed to a callback
+/// instead, with [`PluginManager::set_logger`].
+///
+/// # Note on Threads
+///
+/// `PluginManager` is `Send + Sync`, so it can be wrapped in an `Arc<RwLock<_>>` to load plugins
+/// once at startup and then call into them from many threads. Loading and unloading need `&mut
+/// self`, so they take the write lock, while lookups only need a read lock.
+/// [`ConcurrentPluginManager`](crate::ConcurrentPluginManager) does this wrapping for you.
 pub struct PluginManager {
-    pub plugins: Vec<Box<dyn Plugin>>,
-    pub loaded_libraries: Vec<Library>,
+    // The plugins, and the services they registered in the context, have code in the libraries,
+    // so they are declared (and dropped) before them.
+    plugins: Vec<Box<dyn Plugin>>,
+    context: PluginContext,
+    loaded_libraries: Vec<Box<dyn LoadedLibrary>>,
+    /// The file each entry in `loaded_libraries` was loaded from, kept at the same index.
+    library_paths: Vec<PathBuf>,
+    /// Bookkeeping for each entry in `plugins`, kept at the same index.
+    records: Vec<PluginRecord>,
+    /// The plugins subscribed to each event topic, so events only go to the plugins which want
+    /// them.
+    topics: HashMap<String, Vec<PluginId>>,
+    /// The plugins which didn't subscribe to particular topics, and get every event.
+    every_topic: Vec<PluginId>,
+    pub(crate) loader: Arc<dyn LibraryLoader>,
+    #[cfg(feature = "watch")]
+    pub(crate) watcher: Option<crate::watch::PluginWatcher>,
+    next_id: u64,
+    allow_duplicate_names: bool,
+    /// Whether loading an already loaded file is a no-op rather than an error.
+    ignore_already_loaded: bool,
+    panic_policy: PanicPolicy,
+    /// The symbol plugin constructors are looked up under, unless told otherwise.
+    constructor_symbol: Vec<u8>,
+    /// Passed to `_plugin_create_v2` constructors, so plugins can decline an incompatible host.
+    host_version: u32,
+    /// When set, only plugins with these names may be loaded.
+    allowlist: Option<HashSet<String>>,
+    /// Plugins with these names are never loaded.
+    denylist: HashSet<String>,
+    /// Whether to capture what plugins print while loading and unloading.
+    capture_output: bool,
+    limits: ResourceLimits,
+    on_load: Option<LifecycleHook>,
+    on_unload: Option<LifecycleHook>,
+    admission_hooks: Vec<AdmissionHook>,
+    /// How long each plugin has spent in its callbacks, by name. This outlives the plugins, so
+    /// their `on_plugin_unload()` can be looked up afterwards.
+    timings: HashMap<String, PluginTimings>,
+    /// Where the manager's own messages go.
+    pub(crate) logger: Logger,
 }
 
-impl PluginManager {
+/// A callback notified of a plugin being loaded or unloaded, with how long its `on_plugin_load()`
+/// or `on_plugin_unload()` took and how it went.
+type LifecycleHook = Arc<dyn Fn(&PluginMetadata, Duration, &Result<()>) + Send + Sync>;
+
+/// A check every plugin has to pass before it is loaded, given the plugin and its library.
+type AdmissionHook =
+    Box<dyn Fn(&dyn Plugin, Option<&dyn LoadedLibrary>) -> Result<()> + Send + Sync>;
+
+/// Configures and creates a [`PluginManager`].
+///
+/// Every option starts out with the same default [`PluginManager::new`] uses, so only the ones
+/// which need changing have to be set:
+///
+/// ```
+/// use plugin_framework::{PanicPolicy, PluginManager};
+///
+/// let manager = PluginManager::builder()
+///     .panic_policy(PanicPolicy::Propagate)
+///     .constructor_symbol(b"_my_plugin_create")
+///     .build();
+/// ```
+pub struct PluginManagerBuilder {
+    panic_policy: PanicPolicy,
+    constructor_symbol: Vec<u8>,
+    allow_duplicate_names: bool,
+    ignore_already_loaded: bool,
+    loader: Arc<dyn LibraryLoader>,
+    host_version: u32,
+    allowlist: Option<HashSet<String>>,
+    denylist: HashSet<String>,
+    capture_output: bool,
+    limits: ResourceLimits,
+}
+
+impl PluginManagerBuilder {
+    /// Start from the default configuration.
     pub fn new() -> Self {
         Self {
+            panic_policy: PanicPolicy::default(),
+            constructor_symbol: DEFAULT_CONSTRUCTOR.to_vec(),
+            allow_duplicate_names: false,
+            ignore_already_loaded: false,
+            loader: Arc::new(NativeLoader),
+            host_version: 0,
+            allowlist: None,
+            denylist: HashSet::new(),
+            capture_output: false,
+            limits: ResourceLimits::default(),
+        }
+    }
+
+    /// Choose how plugins which panic are dealt with. Defaults to [`PanicPolicy::Catch`].
+    pub fn panic_policy(mut self, policy: PanicPolicy) -> Self {
+        self.panic_policy = policy;
+        self
+    }
+
+    /// Choose the symbol plugin constructors are looked up under by
+    /// [`PluginManager::load_plugin`] and the folder loaders. Defaults to `_plugin_create`, which
+    /// is what [`declare_plugin!`](crate::declare_plugin) exports.
+    pub fn constructor_symbol(mut self, symbol: &[u8]) -> Self {
+        self.constructor_symbol = symbol.to_vec();
+        self
+    }
+
+    /// Choose whether several plugins may be loaded under the same name, as with
+    /// [`PluginManager::set_allow_duplicate_names`]. Defaults to `false`.
+    pub fn allow_duplicate_names(mut self, allow: bool) -> Self {
+        self.allow_duplicate_names = allow;
+        self
+    }
+
+    /// Choose whether loading a file which plugins are already loaded from (with
+    /// [`PluginManager::load_plugin`] or any of the other ways of loading a single library from a
+    /// file) quietly does nothing, returning the ids of the plugins already loaded from it,
+    /// instead of failing with an [`ErrorKind::AlreadyLoaded`] error. Defaults to `false`.
+    pub fn ignore_already_loaded(mut self, ignore: bool) -> Self {
+        self.ignore_already_loaded = ignore;
+        self
+    }
+
+    /// Choose how plugin libraries are opened. Defaults to [`NativeLoader`].
+    pub fn loader<L: LibraryLoader + 'static>(mut self, loader: L) -> Self {
+        self.loader = Arc::new(loader);
+        self
+    }
+
+    /// Choose the version of the host passed to plugins exporting a `_plugin_create_v2`
+    /// constructor (see [`declare_versioned_plugin!`](crate::declare_versioned_plugin)), which
+    /// may decline to load into a host they don't support. Defaults to `0`.
+    ///
+    /// Unlike [`CORE_ABI_VERSION`], which is about the binary interface of this crate, the
+    /// meaning of this number is entirely up to the host: it is never interpreted by the manager,
+    /// only handed over. A host would typically bump it whenever it changes what it offers
+    /// plugins (e.g. the services in the [`PluginContext`]) in a way existing plugins could trip
+    /// over, and document which versions offer what, so plugin authors know what to accept.
+    pub fn host_version(mut self, version: u32) -> Self {
+        self.host_version = version;
+        self
+    }
+
+    /// Only allow plugins with the given names to be loaded. By default any plugin may be.
+    ///
+    /// A plugin's name isn't known until it has been constructed, so the check happens after its
+    /// library has been opened and its constructor run, but before its `on_plugin_load()` is
+    /// fired. A plugin which isn't allowed is rejected with a `PluginRejected` error (which, when
+    /// loading a folder, is recorded in the [`LoadReport`] like any other failure), and its
+    /// library dropped. If a library exports several plugins, they are all rejected along with
+    /// it.
+    pub fn allowlist<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.allowlist = Some(names.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Never load plugins with the given names, even if they are on the
+    /// [allowlist](PluginManagerBuilder::allowlist). They are rejected in the same way.
+    pub fn denylist<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.denylist = names.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Choose whether to capture everything written to stdout and stderr while plugins'
+    /// `on_plugin_load()` and `on_plugin_unload()` run, and log it instead (at the info level,
+    /// under the plugin's log target), so plugins printing straight to the console don't get
+    /// mixed in with the host's own output. Defaults to `false`.
+    ///
+    /// This redirects the file descriptors of the whole process, so anything other threads print
+    /// in the meantime is captured as well. A plugin's own buffered output is only captured once
+    /// flushed, which `println!` does at the end of every line but `print!` doesn't. A plugin
+    /// still running when a timeout expires (see [`PluginManager::load_plugin_with_timeout`] and
+    /// [`PluginManager::unload_with_timeout`]) keeps the output captured until it returns. This
+    /// is only supported on Unix; elsewhere the output is left alone.
+    #[cfg(feature = "capture")]
+    pub fn capture_output(mut self, capture: bool) -> Self {
+        self.capture_output = capture;
+        self
+    }
+
+    /// Limit what plugins may use while being called. By default there are no limits. See
+    /// [`ResourceLimits`] for what is checked, and why it is only advisory.
+    pub fn resource_limits(mut self, limits: ResourceLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// Create the configured manager.
+    pub fn build(self) -> PluginManager {
+        PluginManager {
             plugins: Vec::new(),
             loaded_libraries: Vec::new(),
+            library_paths: Vec::new(),
+            records: Vec::new(),
+            topics: HashMap::new(),
+            every_topic: Vec::new(),
+            context: PluginContext::new(),
+            loader: self.loader,
+            #[cfg(feature = "watch")]
+            watcher: None,
+            next_id: 0,
+            allow_duplicate_names: self.allow_duplicate_names,
+            ignore_already_loaded: self.ignore_already_loaded,
+            panic_policy: self.panic_policy,
+            constructor_symbol: self.constructor_symbol,
+            host_version: self.host_version,
+            allowlist: self.allowlist,
+            denylist: self.denylist,
+            capture_output: self.capture_output,
+            limits: self.limits,
+            on_load: None,
+            on_unload: None,
+            admission_hooks: Vec::new(),
+            timings: HashMap::new(),
+            logger: Logger::default(),
         }
     }
+}
 
-    /// Load a single plugin, provided the path to the shared library plugin on the system.
-    pub unsafe fn load_plugin<P: AsRef<OsStr>>(&mut self, filename: P) -> Result<()> {
-        type PluginCreate<'a> = unsafe fn() -> &'a mut dyn Plugin;
+impl Default for PluginManagerBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-        let lib = Library::new(filename.as_ref()).chain_err(|| "Unable to load the plugin")?;
+/// What a [`PluginManager`] does when a plugin panics inside one of its callbacks.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum PanicPolicy {
+    /// Catch the panic and carry on. A plugin which panics while loading is discarded and an
+    /// error is returned, one which panics while unloading has the panic reported as an error,
+    /// and one which panics in any other callback is poisoned, so it is never called again.
+    ///
+    /// While a plugin is being called, a panic hook is temporarily installed which logs the name
+    /// of the plugin that panicked before handing over to the host's own hook, which is put back
+    /// afterwards.
+    #[default]
+    Catch,
+    /// Let the panic continue unwinding into the host, as if the plugin had been called directly.
+    Propagate,
+}
 
-        // We need to keep the library around, otherwise our plugin's vtable will point to garbage.
-        // We do this little dance to make sure the library doesn't end up getting moved.
-        self.loaded_libraries.push(lib);
+// The manager is meant to be shareable between threads, since every `Plugin` is `Send + Sync`.
+// Make sure nothing added to it quietly takes that away.
+const _: () = {
+    const fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<PluginManager>();
+};
 
-        let lib = self.loaded_libraries.last().unwrap();
+/// Everything the manager tracks about a loaded plugin, beyond the plugin itself.
+struct PluginRecord {
+    id: PluginId,
+    /// The plugin's name, copied when it was loaded. This is what the manager goes by, so whatever
+    /// the plugin's `name()` borrows from never has to outlive it, and can't change under us.
+    name: String,
+    /// Index into `loaded_libraries` of the library the plugin was created from, if any.
+    library: Option<usize>,
+    /// The file the plugin's library was loaded from, or [`IN_PROCESS_PATH`].
+    path: PathBuf,
+    /// For a plugin loaded from memory, the file (canonicalized) its library was read from, if it
+    /// was read from one. That file counts as loaded, even though the plugin can't be reloaded
+    /// from it.
+    source: Option<PathBuf>,
+    /// Set once the plugin has panicked or overrun its limits, after which it is no longer called.
+    poisoned: bool,
+    /// Disabled plugins stay loaded, but don't receive updates or events.
+    enabled: bool,
+    /// The group the plugin was loaded into, if any.
+    group: Option<String>,
+    /// When the file at `path` was last modified, as of loading the plugin.
+    modified: Option<SystemTime>,
+    /// The commands the plugin listed when it was loaded.
+    commands: Vec<CommandSpec>,
+}
 
-        let constructor: Symbol<PluginCreate> = lib
-            .get(b"_plugin_create")
-            .chain_err(|| "The `_plugin_create` symbol wasn't found.")?;
-        let boxed_raw = constructor();
+impl PluginRecord {
+    /// Whether the plugin's library was loaded from a file, which it could be loaded from again.
+    fn has_file(&self) -> bool {
+        self.library.is_some() && self.path != Path::new(IN_MEMORY_PATH)
+    }
+}
 
-        let plugin = Box::from_raw(boxed_raw);
-        debug!("Loaded Plugin: {}", plugin.name());
-        plugin.on_plugin_load();
-        self.plugins.push(plugin);
+impl PluginManager {
+    /// Create a manager with the default configuration. This is shorthand for
+    /// `PluginManager::builder().build()`.
+    pub fn new() -> Self {
+        PluginManagerBuilder::new().build()
+    }
 
-        Ok(())
+    /// Start configuring a manager. See [`PluginManagerBuilder`] for the available options.
+    pub fn builder() -> PluginManagerBuilder {
+        PluginManagerBuilder::new()
+    }
+
+    /// Create a manager which handles panicking plugins according to `policy`.
+    pub fn with_panic_policy(policy: PanicPolicy) -> Self {
+        PluginManagerBuilder::new().panic_policy(policy).build()
+    }
+
+    /// Create a manager which opens plugin libraries through `loader` instead of the default
+    /// [`NativeLoader`]. This is mostly useful for testing the manager against fake libraries.
+    pub fn with_loader<L: LibraryLoader + 'static>(loader: L) -> Self {
+        PluginManagerBuilder::new().loader(loader).build()
+    }
+
+    /// The host services shared with every plugin.
+    pub fn context(&self) -> &PluginContext {
+        &self.context
+    }
+
+    /// The host services shared with every plugin, for registering services before loading.
+    pub fn context_mut(&mut self) -> &mut PluginContext {
+        &mut self.context
+    }
+
+    /// Choose whether several plugins may be loaded under the same name. This is off by default,
+    /// so loading a plugin whose name is already taken fails with a "duplicate plugin name" error.
+    pub fn set_allow_duplicate_names(&mut self, allow: bool) {
+        self.allow_duplicate_names = allow;
+    }
+
+    /// Register a callback to be notified every time a plugin's `on_plugin_load()` has been
+    /// fired, with the plugin's metadata, how long the call took, and whether it succeeded (a
+    /// failure means the plugin wasn't loaded). This replaces any callback registered before.
+    ///
+    /// This is meant for instrumentation such as metrics, so it is called in between loading
+    /// steps and should be quick. Plugins rejected before `on_plugin_load()` is fired, e.g.
+    /// because their library couldn't be opened, aren't reported.
+    pub fn set_on_load<F>(&mut self, hook: F)
+    where
+        F: Fn(&PluginMetadata, Duration, &Result<()>) + Send + Sync + 'static,
+    {
+        self.on_load = Some(Arc::new(hook));
+    }
+
+    /// Register a callback to be notified every time a plugin's `on_plugin_unload()` has been
+    /// fired, like [`PluginManager::set_on_load`]. The plugin is unloaded whether or not it
+    /// succeeded.
+    pub fn set_on_unload<F>(&mut self, hook: F)
+    where
+        F: Fn(&PluginMetadata, Duration, &Result<()>) + Send + Sync + 'static,
+    {
+        self.on_unload = Some(Arc::new(hook));
+    }
+
+    /// Send every message the manager logs (about plugins loading, unloading, panicking, ...) to
+    /// `logger` instead of the `log` facade, for hosts which don't set up a global `log` logger.
+    /// The callback is given every message whatever its level, so any filtering is up to it.
+    /// Until this is called, messages go to `log` as described in the
+    /// [note on logging](PluginManager#note-on-logging).
+    ///
+    /// This only covers what the manager itself logs; plugins' own log calls are unaffected. A
+    /// folder being watched for changes (with the `watch` feature) has any errors watching it
+    /// logged wherever messages went when watching started, so this should be called first.
+    pub fn set_logger<F>(&mut self, logger: F)
+    where
+        F: Fn(LogLevel, &str) + Send + Sync + 'static,
+    {
+        self.logger = Logger::new(logger);
+    }
+
+    /// Register a check which every plugin has to pass before it is loaded, on top of the
+    /// manager's own. The hook is given each freshly constructed plugin along with the library it
+    /// came from (or `None` for plugins [registered](PluginManager::register_plugin) directly),
+    /// e.g. to look up the extra functions the host expects the library to export. Returning an
+    /// error rejects the plugin's whole library before any of its plugins' `on_plugin_load()` is
+    /// fired, and the error is returned from the load.
+    ///
+    /// Hooks run in the order they were registered, after the manager's own checks (duplicate
+    /// names, the allowlist and denylist, and dependencies).
+    pub fn register_hook<F>(&mut self, hook: F)
+    where
+        F: Fn(&dyn Plugin, Option<&dyn LoadedLibrary>) -> Result<()> + Send + Sync + 'static,
+    {
+        self.admission_hooks.push(Box::new(hook));
+    }
+
+    /// Register a policy which can veto plugins going by their metadata alone, e.g. to only load
+    /// plugins from a trusted author. This is a simpler form of
+    /// [`PluginManager::register_hook`], and runs along with the other hooks, in the order they
+    /// were all registered; the first to reject a plugin stops the rest from running. Returning
+    /// `Err` rejects the plugin's whole library before any `on_plugin_load()` is fired, with a
+    /// `PluginVetoed` error carrying the reason.
+    pub fn register_interceptor<F>(&mut self, interceptor: F)
+    where
+        F: Fn(&PluginMetadata) -> std::result::Result<(), String> + Send + Sync + 'static,
+    {
+        self.register_hook(move |plugin, _| {
+            interceptor(&plugin.metadata())
+                .map_err(|reason| ErrorKind::PluginVetoed(plugin.name().to_owned(), reason).into())
+        });
+    }
+
+    /// Load a single plugin, provided the path to the shared library plugin on the system. The
+    /// returned [`PluginId`] can be used to refer to the plugin later on.
+    ///
+    /// # Safety
+    ///
+    /// Loading a library runs its initialization routines, and the constructor symbol
+    /// (`_plugin_create`, unless changed with [`PluginManagerBuilder::constructor_symbol`]) is
+    /// trusted to have the expected signature. Only load libraries built against this crate.
+    ///
+    /// Before the constructor is called, the library's exported `_plugin_abi_version` is compared
+    /// against [`CORE_ABI_VERSION`]. If it is missing or doesn't match, an error is returned and
+    /// the library is not kept around. With the `semver` feature enabled, the library's
+    /// `_plugin_api_semver` is then checked against
+    /// [`PLUGIN_API_VERSION`](crate::PLUGIN_API_VERSION) in the same way, failing with an
+    /// [`ErrorKind::IncompatibleApi`] error. Libraries which don't export a `_plugin_api_semver`
+    /// skip that check.
+    ///
+    /// If the plugin's `on_plugin_load()` returns an error, the load is treated as failed: the
+    /// plugin and its library are discarded and the error is returned. A panic inside
+    /// `on_plugin_load()` is caught and handled the same way. This relies on wrapping the plugin in
+    /// `AssertUnwindSafe`, which is only sound because a plugin that panicked is never used again.
+    /// A panic inside the plugin's constructor is caught too, and the library dropped, as long as
+    /// the panic can be caught at all (see the
+    /// [ABI contract](crate::declare_plugin#the-abi-contract)).
+    ///
+    /// The library is only kept loaded once its plugin has been registered, so a load which fails
+    /// at any step doesn't leave the library behind.
+    ///
+    /// Unless [`PluginManager::set_allow_duplicate_names`] has been used to opt out, a plugin
+    /// whose name is already taken is rejected before its `on_plugin_load()` is fired. So is a
+    /// plugin whose [dependencies](Plugin::dependencies) aren't all loaded yet, with a
+    /// `MissingDependency` error.
+    ///
+    /// Loading a file which plugins are already loaded from fails with an
+    /// [`ErrorKind::AlreadyLoaded`] error before the library is opened again, whether or not
+    /// duplicate names are allowed, and so does every other way of loading a single library from
+    /// a file.
+    /// Paths are compared once canonicalized, so `./x.so`, the absolute path to it and a symlink
+    /// to it are all the same file. This can be turned into a no-op with
+    /// [`PluginManagerBuilder::ignore_already_loaded`].
+    ///
+    /// If the library exports several plugins through `_plugin_create_all` (see
+    /// [`declare_plugins!`](crate::declare_plugins)), they are all loaded, and the id of the
+    /// first one is returned. Use [`PluginManager::load_library`] to get every id.
+    ///
+    /// If the library exports a `_plugin_create_v2` constructor instead (see
+    /// [`declare_versioned_plugin!`](crate::declare_versioned_plugin)), it is handed the
+    /// [host version](PluginManagerBuilder::host_version) and may decline to create its plugin,
+    /// in which case an [`ErrorKind::IncompatibleHost`] error is returned and the library dropped.
+    ///
+    /// # WebAssembly Plugins
+    ///
+    /// With the `wasm` feature enabled, a file ending in `.wasm` is compiled and run as a
+    /// sandboxed WebAssembly module instead, through `wasmtime`, and the folder loaders pick up
+    /// `.wasm` files as well. Once loaded it is managed like any other plugin, and can be looked
+    /// up, unloaded and reloaded the same way. The module is given no imports, and has to export:
+    ///
+    /// - its `memory`,
+    /// - `plugin_name() -> i64`, returning the address of the plugin's UTF-8 name in its memory in
+    ///   the upper 32 bits and its length in the lower 32 bits,
+    ///
+    /// and may export `on_plugin_load() -> i32` and `on_plugin_unload() -> i32`, which return zero
+    /// on success and anything else to fail. A trap is reported as a failure too. No other
+    /// callbacks are bridged, and WebAssembly plugins have no access to the [`PluginContext`].
+    /// Modules are always read from disk, bypassing the manager's [`LibraryLoader`].
+    ///
+    /// The sandbox only covers the plugin itself: the safety requirements above still apply to
+    /// the native libraries loaded alongside it, but a WebAssembly module can't break them.
+    pub unsafe fn load_plugin<P: AsRef<OsStr>>(&mut self, filename: P) -> Result<PluginId> {
+        self.try_load_plugin(filename)
+    }
+
+    /// Load a single plugin like [`PluginManager::load_plugin`] (which does exactly this), with
+    /// the guarantee that a failed load leaves the manager as it was before the call: no library
+    /// is kept, no plugin is registered, no [`PluginId`] is used up, and the plugin's
+    /// [timings](PluginManager::timings) and any services it registered are rolled back. This
+    /// holds whichever step fails, from opening the library or looking up its constructor to a
+    /// rejected name or an error from `on_plugin_load()`.
+    ///
+    /// What can't be undone is anything the plugin did by itself while it was loaded, including
+    /// replacing or removing services in the [`PluginContext`] which it didn't register, and the
+    /// [lifecycle hooks](PluginManager::set_on_load) having been called. When a library exports
+    /// several plugins and one of them fails, the ones loaded before it are unloaded again, which
+    /// fires their `on_plugin_unload()`.
+    ///
+    /// The same holds for every other way of loading plugins, for each library on its own.
+    ///
+    /// # Safety
+    ///
+    /// The same requirements as [`PluginManager::load_plugin`] apply.
+    pub unsafe fn try_load_plugin<P: AsRef<OsStr>>(&mut self, filename: P) -> Result<PluginId> {
+        self.load_library(filename).map(|ids| ids[0])
+    }
+
+    /// Load a single plugin like [`PluginManager::load_plugin`], returning the plugin itself
+    /// rather than its id, for when it is going to be used straight away. The plugin is borrowed
+    /// from the manager, which can't be changed again until the borrow ends.
+    ///
+    /// # Safety
+    ///
+    /// The same requirements as [`PluginManager::load_plugin`] apply.
+    pub unsafe fn load_plugin_ref<P: AsRef<OsStr>>(&mut self, filename: P) -> Result<&dyn Plugin> {
+        let id = self.load_plugin(filename)?;
+        Ok(self
+            .get_plugin_by_id(id)
+            .expect("a plugin which was just loaded is still loaded"))
+    }
+
+    /// Load every plugin exported by a library, returning their ids in the order the library
+    /// created them. This is the same as [`PluginManager::load_plugin`], which is more convenient
+    /// for libraries exporting a single plugin.
+    ///
+    /// The constructor is looked up as `_plugin_create_all` first, which is expected to be
+    /// exported as described on [`declare_plugins!`](crate::declare_plugins), handing its plugins
+    /// back one at a time through a [`PluginRegistrar`](crate::PluginRegistrar), and falling back
+    /// to the single plugin constructor if the library doesn't export it. All the plugins from one
+    /// library share it, so the library is only dropped once the last of them is unloaded. The
+    /// library is loaded as a whole: if any of its plugins fails to load, the ones loaded before it
+    /// are unloaded again and the error is returned.
+    ///
+    /// # Safety
+    ///
+    /// The same requirements as [`PluginManager::load_plugin`] apply, with `_plugin_create_all`
+    /// trusted to have the signature above.
+    pub unsafe fn load_library<P: AsRef<OsStr>>(&mut self, filename: P) -> Result<Vec<PluginId>> {
+        if let Some(ids) = self.check_not_loaded(filename.as_ref())? {
+            return Ok(ids);
+        }
+
+        let candidate = self.prepare(filename.as_ref())?;
+        self.activate(candidate)
+    }
+
+    /// Load a plugin like [`PluginManager::load_plugin`], and put it (along with any other plugins
+    /// exported by the same library) in a `group`, so it can be looked up with
+    /// [`PluginManager::plugins_in_group`] and unloaded with [`PluginManager::unload_group`]
+    /// together with the rest of the group.
+    ///
+    /// # Safety
+    ///
+    /// The same requirements as [`PluginManager::load_plugin`] apply.
+    pub unsafe fn load_plugin_in_group<P: AsRef<OsStr>>(
+        &mut self,
+        filename: P,
+        group: &str,
+    ) -> Result<PluginId> {
+        let start = self.plugins.len();
+        let id = self.load_plugin(filename)?;
+        self.assign_group(start, group);
+        Ok(id)
+    }
+
+    /// Load a single plugin like [`PluginManager::load_plugin`], but look up the constructor under
+    /// the given symbol name instead of the manager's configured one. The constructor is expected
+    /// to have the same signature, and `_plugin_create_all` isn't looked for.
+    ///
+    /// # Safety
+    ///
+    /// The same requirements as [`PluginManager::load_plugin`] apply, with `symbol` trusted to
+    /// have the constructor's signature.
+    pub unsafe fn load_plugin_with_symbol<P: AsRef<OsStr>>(
+        &mut self,
+        filename: P,
+        symbol: &[u8],
+    ) -> Result<PluginId> {
+        if let Some(ids) = self.check_not_loaded(filename.as_ref())? {
+            return Ok(ids[0]);
+        }
+
+        let candidate = Self::construct(
+            &*self.loader,
+            filename.as_ref(),
+            symbol,
+            false,
+            self.host_version,
+            None,
+            &self.logger,
+        )?;
+        self.activate(candidate).map(|ids| ids[0])
+    }
+
+    /// Register a plugin which is already part of the host, e.g. one compiled into a statically
+    /// linked build or a mock in a test, without loading any library. The returned [`PluginId`]
+    /// can be used to refer to the plugin later on.
+    ///
+    /// Otherwise the plugin goes through the same steps as one loaded with
+    /// [`PluginManager::load_plugin`]: it is rejected if its name is already taken, and its
+    /// `on_plugin_load()` is fired before it is registered, failing the registration (and
+    /// dropping the plugin) if it returns an error or panics. It is unloaded like any other
+    /// plugin, but has no [path](PluginManager::plugin_path) or
+    /// [symbols](PluginManager::get_symbol), and is left alone by
+    /// [`PluginManager::reload_all`].
+    pub fn register_plugin(&mut self, plugin: Box<dyn Plugin>) -> Result<PluginId> {
+        debug!(
+            self.logger,
+            target: &log_target(plugin.name()),
+            "Registering Plugin: {} (version {})",
+            plugin.name(),
+            plugin.version()
+        );
+
+        let candidate = Candidate {
+            plugins: vec![plugin],
+            library: None,
+            path: PathBuf::from(IN_PROCESS_PATH),
+        };
+        self.activate(candidate).map(|ids| ids[0])
+    }
+
+    /// Check that a plugin library could be loaded, without registering it with any manager: the
+    /// library is opened, its ABI version checked and its plugin constructed, and then everything
+    /// is dropped again. The plugin's metadata is returned, e.g. to be shown before deciding
+    /// whether to load it for real.
+    ///
+    /// The plugin's `on_plugin_load()` and `on_plugin_unload()` are never fired, so this says
+    /// nothing about whether it will initialise successfully. The library is opened with a
+    /// [`NativeLoader`] and the default constructor symbol, and a `_plugin_create_v2` constructor
+    /// is given a host version of `0`. A library exporting several plugins has the metadata of
+    /// the first one returned.
+    ///
+    /// # Safety
+    ///
+    /// The library's initialization routines and the plugin's constructor run as they would for
+    /// [`PluginManager::load_plugin`], so the same requirements apply.
+    pub unsafe fn validate_plugin<P: AsRef<OsStr>>(filename: P) -> Result<PluginMetadata> {
+        let candidate = Self::construct(
+            &NativeLoader,
+            filename.as_ref(),
+            DEFAULT_CONSTRUCTOR,
+            true,
+            0,
+            None,
+            &Logger::default(),
+        )?;
+
+        // The metadata is owned, so it can outlive the library.
+        Ok(candidate.plugins[0].metadata())
+    }
+
+    /// Load a single plugin like [`PluginManager::load_plugin`], but first check that its library
+    /// exports every one of `required_symbols`. This lets a host enforce a contract beyond the
+    /// [`Plugin`] trait, such as extra functions it means to look up later with
+    /// [`PluginManager::get_symbol`].
+    ///
+    /// The check happens after the ABI version check but before the constructor is called, so if
+    /// a symbol is missing, a `SymbolNotFound` error naming it is returned and the library is
+    /// dropped without constructing anything.
+    ///
+    /// # Safety
+    ///
+    /// The same requirements as [`PluginManager::load_plugin`] apply.
+    pub unsafe fn load_plugin_requiring<P: AsRef<OsStr>>(
+        &mut self,
+        filename: P,
+        required_symbols: &[&[u8]],
+    ) -> Result<PluginId> {
+        let filename = filename.as_ref();
+        if let Some(ids) = self.check_not_loaded(filename)? {
+            return Ok(ids[0]);
+        }
+
+        let library = Self::open(&*self.loader, filename)?;
+        for symbol in required_symbols {
+            library.symbol(symbol)?;
+        }
+
+        let candidate = Self::instantiate(
+            library,
+            Path::new(filename),
+            &self.constructor_symbol,
+            true,
+            self.host_version,
+            None,
+            &self.logger,
+        )?;
+        self.activate(candidate).map(|ids| ids[0])
+    }
+
+    /// Load a single plugin like [`PluginManager::load_plugin`], from the contents of a library
+    /// which is already in memory (for instance, one which was just downloaded), rather than from
+    /// a file the caller has to manage.
+    ///
+    /// On Linux, the library is copied into an anonymous `memfd` and opened from there, so it
+    /// never touches the filesystem. On other platforms, or on kernels without `memfd_create`, it
+    /// falls back to writing a temporary file only the current user can read. The file is
+    /// deleted as soon as the library has been opened on Unix, and once the library is dropped on
+    /// Windows, which doesn't allow deleting a loaded library.
+    ///
+    /// The plugin reports `<memory>` as its [path](PluginManager::plugin_path), so it can't be
+    /// reloaded from there. Since there is no file to go by, this is also the one loader which
+    /// doesn't check whether the library is already loaded (see
+    /// [`PluginManagerBuilder::ignore_already_loaded`]): loading the same bytes twice loads the
+    /// library twice.
+    ///
+    /// The loaders which check a library before loading it (with the `verify` and `signature`
+    /// features) go through here too. They read the file once, check what they read, and load
+    /// the library from those very bytes, so the file being swapped out after it was checked
+    /// makes no difference. Their plugins report `<memory>` as well, so they can't be reloaded
+    /// from the file without it being checked again, but the file they were read from does count
+    /// as already loaded.
+    ///
+    /// # Safety
+    ///
+    /// The same requirements as [`PluginManager::load_plugin`] apply to the library in `bytes`.
+    pub unsafe fn load_plugin_from_bytes(&mut self, bytes: &[u8]) -> Result<PluginId> {
+        self.load_bytes(bytes).map(|ids| ids[0])
+    }
+
+    /// Load the library at `filename` like [`PluginManager::load_plugin_from_bytes`], once the
+    /// bytes which were read from it have passed `check`. The file counts as loaded afterwards.
+    #[cfg(any(feature = "verify", feature = "signature"))]
+    pub(crate) unsafe fn load_plugin_checked(
+        &mut self,
+        filename: &OsStr,
+        check: impl FnOnce(&Path, &[u8]) -> Result<()>,
+    ) -> Result<PluginId> {
+        if let Some(ids) = self.check_not_loaded(filename)? {
+            return Ok(ids[0]);
+        }
+
+        let path = Path::new(filename);
+        let contents = fs::read(path).chain_err(|| ErrorKind::LibraryLoadFailed(path.into()))?;
+        check(path, &contents)?;
+
+        let ids = self.load_bytes(&contents)?;
+        let source = fs::canonicalize(path).ok();
+        for record in self.records.iter_mut().filter(|r| ids.contains(&r.id)) {
+            record.source = source.clone();
+        }
+        Ok(ids[0])
+    }
+
+    /// Load every plugin in the library in `bytes`, returning their ids.
+    unsafe fn load_bytes(&mut self, bytes: &[u8]) -> Result<Vec<PluginId>> {
+        let image = LibraryImage::new(bytes)?;
+        let library = Self::open(&*self.loader, image.path().as_os_str())?;
+        let library = image.opened(library);
+
+        let candidate = Self::instantiate(
+            library,
+            Path::new(IN_MEMORY_PATH),
+            &self.constructor_symbol,
+            true,
+            self.host_version,
+            None,
+            &self.logger,
+        )?;
+        self.activate(candidate)
+    }
+
+    /// Load a single plugin like [`PluginManager::load_plugin`], handing it some startup
+    /// configuration.
+    ///
+    /// The configuration is passed to the library's `_plugin_create_with_config` constructor,
+    /// which must be exported as:
+    ///
+    /// ```text
+    /// #[no_mangle]
+    /// pub extern "C-unwind" fn _plugin_create_with_config(config: &PluginConfig) -> *mut dyn Plugin
+    /// ```
+    ///
+    /// That is, with the same [convention](crate::declare_plugin#the-abi-contract) as
+    /// `_plugin_create`, apart from the extra argument. The
+    /// configuration is only borrowed for the duration of the call, so the plugin has to copy out
+    /// anything it wants to keep. If the library doesn't export `_plugin_create_with_config`, it
+    /// falls back to the manager's plain constructors and the configuration is ignored.
+    ///
+    /// # Safety
+    ///
+    /// The same requirements as [`PluginManager::load_plugin`] apply, with
+    /// `_plugin_create_with_config` trusted to have the signature above.
+    pub unsafe fn load_plugin_with_config<P: AsRef<OsStr>>(
+        &mut self,
+        filename: P,
+        config: &PluginConfig,
+    ) -> Result<PluginId> {
+        if let Some(ids) = self.check_not_loaded(filename.as_ref())? {
+            return Ok(ids[0]);
+        }
+
+        let candidate = Self::construct(
+            &*self.loader,
+            filename.as_ref(),
+            &self.constructor_symbol,
+            true,
+            self.host_version,
+            Some(config),
+            &self.logger,
+        )?;
+        self.activate(candidate).map(|ids| ids[0])
+    }
+
+    /// Load a single plugin like [`PluginManager::load_plugin`], but give up if opening the
+    /// library, constructing the plugin and firing its `on_plugin_load()` don't finish within
+    /// `timeout`. A `LoadTimeout` error is returned in that case, so a plugin which hangs while
+    /// loading can't hang the host along with it.
+    ///
+    /// The work is done on a separate thread, which can't be stopped once it is running, so this
+    /// is what happens to a plugin which takes too long:
+    ///
+    /// - If it is still being opened or constructed, it is dropped as soon as that finishes,
+    ///   along with its library.
+    /// - If it is still inside `on_plugin_load()`, its library is deliberately leaked, since the
+    ///   plugin's code keeps running and dropping the library from under it would be undefined
+    ///   behaviour. The id it was going to be given is handed out again.
+    ///
+    /// Since a plugin which never finishes would never give it back, `on_plugin_load()` isn't
+    /// handed the manager's [`PluginContext`], but one of its own which starts out empty, so the
+    /// host's services can't be looked up from it. Whatever the plugin registers there is moved
+    /// into the manager's context once it finishes, and is thrown away along with the plugin if it
+    /// doesn't. Messages it sends are queued along with everyone else's, as usual.
+    ///
+    /// # Safety
+    ///
+    /// The same requirements as [`PluginManager::load_plugin`] apply. On top of that, the
+    /// library has to be safe to open, and its plugin safe to construct and load, from a thread
+    /// other than the caller's.
+    ///
+    /// A library exporting several plugins is loaded like [`PluginManager::load_library`], all
+    /// within the same `timeout`, and the id of its first plugin is returned.
+    pub unsafe fn load_plugin_with_timeout<P: AsRef<OsStr>>(
+        &mut self,
+        filename: P,
+        timeout: Duration,
+    ) -> Result<PluginId> {
+        if let Some(ids) = self.check_not_loaded(filename.as_ref())? {
+            return Ok(ids[0]);
+        }
+
+        let path = PathBuf::from(filename.as_ref());
+        let deadline = Instant::now() + timeout;
+
+        // Something the plugin produces after we've given up on it is simply dropped, once
+        // nothing is running inside its library any more.
+        let (sender, receiver) = mpsc::channel();
+        let loader = Arc::clone(&self.loader);
+        let symbol = self.constructor_symbol.clone();
+        let host_version = self.host_version;
+        let logger = self.logger.clone();
+        let filename = path.clone();
+        let worker = thread::spawn(move || {
+            let candidate = Self::construct(
+                &*loader,
+                filename.as_os_str(),
+                &symbol,
+                true,
+                host_version,
+                None,
+                &logger,
+            );
+            let _ = sender.send(candidate);
+        });
+
+        let candidate =
+            match receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok(candidate) => candidate?,
+                Err(RecvTimeoutError::Timeout) => bail!(ErrorKind::LoadTimeout(path, timeout)),
+                Err(RecvTimeoutError::Disconnected) => {
+                    let cause = worker.join().unwrap_err();
+                    bail!(ErrorKind::ConstructorPanicked(
+                        panic_message(&cause).to_owned()
+                    ))
+                }
+            };
+
+        let next_id = self.next_id;
+        let names = candidate.names();
+        let ids = self.admit(&candidate, &names)?;
+        let Candidate {
+            plugins, library, ..
+        } = candidate;
+
+        let (sender, receiver) = mpsc::channel();
+        let mut context = self.context.fork();
+        let capture = self.capture_output;
+        let logger = self.logger.clone();
+        thread::spawn(move || {
+            let outcomes = fire_load_all(&plugins, &names, &mut context, &ids, capture, &logger);
+            let _ = sender.send((plugins, names, context, ids, outcomes));
+        });
+
+        match receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            Ok((plugins, names, context, ids, outcomes)) => {
+                self.context.merge(context);
+                let candidate = Candidate {
+                    plugins,
+                    library,
+                    path,
+                };
+                let result = self.settle(candidate, names, ids, outcomes);
+                if result.is_err() {
+                    self.next_id = next_id;
+                }
+                result.map(|ids| ids[0])
+            }
+            Err(_) => {
+                warn!(
+                    self.logger,
+                    "The plugin at {:?} is stuck in on_plugin_load, leaking its library", path
+                );
+                mem::forget(library);
+                self.next_id = next_id;
+                bail!(ErrorKind::LoadTimeout(path, timeout))
+            }
+        }
+    }
+
+    /// Load a single plugin like [`PluginManager::load_plugin`], but retry opening its library if
+    /// that fails, e.g. because the file is still being synced to a network filesystem. Up to
+    /// `attempts` attempts are made in total, waiting `backoff` after the first failure and twice
+    /// as long after each subsequent one, but never more than a minute altogether.
+    ///
+    /// Only failures to open the library (`LibraryLoadFailed`) are retried, since the operating
+    /// system doesn't tell transient failures apart from permanent ones like a corrupt file.
+    /// Anything which goes wrong once the library is open, such as a missing symbol or a
+    /// mismatched ABI version, won't go away by trying again, so it is returned straight away. If
+    /// every attempt fails, the error from the last one is returned.
+    ///
+    /// # Safety
+    ///
+    /// The same requirements as [`PluginManager::load_plugin`] apply.
+    pub unsafe fn load_plugin_with_retry<P: AsRef<OsStr>>(
+        &mut self,
+        filename: P,
+        attempts: u32,
+        backoff: Duration,
+    ) -> Result<PluginId> {
+        let filename = filename.as_ref();
+        if let Some(ids) = self.check_not_loaded(filename)? {
+            return Ok(ids[0]);
+        }
+
+        let mut delay = backoff;
+        let mut waited = Duration::ZERO;
+        let mut attempt = 1;
+
+        let library = loop {
+            match Self::open(&*self.loader, filename) {
+                Ok(library) => break library,
+                Err(e)
+                    if attempt < attempts
+                        && waited < MAX_RETRY_WAIT
+                        && matches!(e.kind(), ErrorKind::LibraryLoadFailed(_)) =>
+                {
+                    let pause = delay.min(MAX_RETRY_WAIT - waited);
+                    debug!(
+                        self.logger,
+                        "Failed to open {:?} (attempt {} of {}), retrying in {:?}: {}",
+                        filename,
+                        attempt,
+                        attempts,
+                        pause,
+                        e
+                    );
+                    thread::sleep(pause);
+
+                    waited += pause;
+                    delay = delay.saturating_mul(2);
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        };
+
+        let candidate = Self::instantiate(
+            library,
+            Path::new(filename),
+            &self.constructor_symbol,
+            true,
+            self.host_version,
+            None,
+            &self.logger,
+        )?;
+        self.activate(candidate).map(|ids| ids[0])
     }
 
     /// Load a set of plugins, provided a path to the folder containing shared library plugins.
-    pub unsafe fn load_plugins<P: AsRef<OsStr>>(&mut self, file_path: P) -> Result<()> {
-        todo!()
+    ///
+    /// The scan is shallow: only the files directly inside the folder are considered, and
+    /// subdirectories are skipped. Files which don't carry the platform's shared library extension
+    /// are ignored. Every candidate is attempted, even if an earlier one fails; the returned
+    /// [`LoadReport`] records which files loaded and which failed, along with their errors, and
+    /// the names of the plugins this call loaded. Plugins that loaded successfully stay registered
+    /// regardless of the failures.
+    ///
+    /// Files which a loaded plugin already came from are skipped, so scanning the same folder
+    /// again only loads the files added since. Paths are compared once canonicalized, so the
+    /// same file is recognised however it was spelled when it was loaded. The other folder
+    /// loaders skip loaded files in the same way.
+    ///
+    /// Every plugin in the folder is constructed first, and then their `on_plugin_load()` methods
+    /// are fired in dependency order (see [`Plugin::dependencies`]), so a plugin is always loaded
+    /// after the plugins it depends on. Dependencies may also be on plugins which are already
+    /// loaded; a plugin depending on one which is neither fails with a `MissingDependency` error,
+    /// as does one whose dependency failed to load.
+    ///
+    /// An error is returned if the folder itself can't be read, or if the plugins' dependencies
+    /// form a cycle (a `DependencyCycle` error, naming the plugins involved). In that case none of
+    /// the plugins in the folder are loaded.
+    ///
+    /// # Safety
+    ///
+    /// Every candidate is loaded as if by [`PluginManager::load_plugin`], so the same requirements
+    /// apply to each library in the folder.
+    pub unsafe fn load_plugins<P: AsRef<OsStr>>(&mut self, file_path: P) -> Result<LoadReport> {
+        let mut report = LoadReport::default();
+        let mut paths = Vec::new();
+        Self::scan_dir(
+            Path::new(file_path.as_ref()),
+            None,
+            &mut paths,
+            &mut report,
+            &self.logger,
+        )?;
+        skip_loaded(self.plugin_paths(), &mut paths, &self.logger);
+        self.load_all(paths, &mut report)?;
+
+        Ok(report)
+    }
+
+    /// Load a folder of plugins like [`PluginManager::load_plugins`], but only the files whose name
+    /// matches a glob `pattern` such as `plugin_*.so`, so plugins can share a folder with other
+    /// shared libraries. Other files are skipped silently.
+    ///
+    /// In the pattern, `*` matches any run of characters (including none) and `?` any single
+    /// character. Only the file name is matched, not the rest of the path. Files also have to
+    /// pass the extension filter, so a pattern can narrow down which libraries are loaded, but not
+    /// pick up files which aren't libraries.
+    ///
+    /// # Safety
+    ///
+    /// The same requirements as [`PluginManager::load_plugins`] apply.
+    pub unsafe fn load_plugins_matching<P: AsRef<OsStr>>(
+        &mut self,
+        dir: P,
+        pattern: &str,
+    ) -> Result<LoadReport> {
+        let mut report = LoadReport::default();
+        let mut paths = Vec::new();
+        Self::scan_dir(
+            Path::new(dir.as_ref()),
+            None,
+            &mut paths,
+            &mut report,
+            &self.logger,
+        )?;
+        paths.retain(|path| {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            let matches = matches_glob(pattern, &name);
+            if !matches {
+                trace!(
+                    self.logger,
+                    "Skipping {:?}, which doesn't match {:?}",
+                    path,
+                    pattern
+                );
+            }
+            matches
+        });
+        skip_loaded(self.plugin_paths(), &mut paths, &self.logger);
+        self.load_all(paths, &mut report)?;
+
+        Ok(report)
+    }
+
+    /// Load a folder of plugins like [`PluginManager::load_plugins`], putting every plugin loaded
+    /// from it in a `group` (see [`PluginManager::load_plugin_in_group`]).
+    ///
+    /// # Safety
+    ///
+    /// The same requirements as [`PluginManager::load_plugins`] apply.
+    pub unsafe fn load_plugins_in_group<P: AsRef<OsStr>>(
+        &mut self,
+        file_path: P,
+        group: &str,
+    ) -> Result<LoadReport> {
+        let start = self.plugins.len();
+        let report = self.load_plugins(file_path)?;
+        self.assign_group(start, group);
+        Ok(report)
+    }
+
+    /// Put every plugin from `start` onwards, i.e. the ones loaded since the manager had that many,
+    /// in `group`.
+    fn assign_group(&mut self, start: usize, group: &str) {
+        for record in &mut self.records[start..] {
+            record.group = Some(group.to_owned());
+        }
+    }
+
+    /// Load every plugin found in a folder and all of its subfolders.
+    ///
+    /// The same extension filter as [`PluginManager::load_plugins`] is applied at every level.
+    /// Each directory is only visited once (compared by its canonical path), so symlink loops
+    /// won't cause the walk to spin forever. Subfolders which can't be read are recorded as
+    /// failures in the returned [`LoadReport`], whose `loaded` list can be used to verify the tree
+    /// was traversed fully. Plugins are loaded in dependency order across the whole tree.
+    ///
+    /// # Safety
+    ///
+    /// Every candidate is loaded as if by [`PluginManager::load_plugin`], so the same requirements
+    /// apply to each library in the tree.
+    pub unsafe fn load_plugins_recursive<P: AsRef<OsStr>>(&mut self, dir: P) -> Result<LoadReport> {
+        let mut visited = HashSet::new();
+        let mut report = LoadReport::default();
+        let mut paths = Vec::new();
+        Self::scan_dir(
+            Path::new(dir.as_ref()),
+            Some(&mut visited),
+            &mut paths,
+            &mut report,
+            &self.logger,
+        )?;
+        skip_loaded(self.plugin_paths(), &mut paths, &self.logger);
+        self.load_all(paths, &mut report)?;
+
+        Ok(report)
+    }
+
+    /// Load a folder of plugins like [`PluginManager::load_plugins`], but open the libraries
+    /// concurrently on a pool of threads to speed up startup when there are many of them.
+    ///
+    /// Only opening the libraries (the dynamic linker's work, including checking their ABI
+    /// version) happens in parallel. Constructing the plugins and firing their `on_plugin_load()`
+    /// still happens on the calling thread, one plugin at a time, so plugins never have to cope
+    /// with being initialised concurrently. Candidates are processed in order of their paths, so
+    /// the resulting order of [`PluginManager::plugins`] is deterministic.
+    ///
+    /// # Safety
+    ///
+    /// Every candidate is loaded as if by [`PluginManager::load_plugin`], so the same requirements
+    /// apply to each library in the folder. On top of that, every library has to be safe to open
+    /// concurrently with the others.
+    pub unsafe fn load_plugins_parallel<P: AsRef<OsStr>>(
+        &mut self,
+        file_path: P,
+    ) -> Result<LoadReport> {
+        let mut report = LoadReport::default();
+        let mut paths = Vec::new();
+        Self::scan_dir(
+            Path::new(file_path.as_ref()),
+            None,
+            &mut paths,
+            &mut report,
+            &self.logger,
+        )?;
+        skip_loaded(self.plugin_paths(), &mut paths, &self.logger);
+        paths.sort();
+
+        let threads = thread::available_parallelism().map_or(1, NonZeroUsize::get);
+        let chunk_size = paths.len().div_ceil(threads).max(1);
+
+        let opened: Vec<Result<Box<dyn LoadedLibrary>>> = thread::scope(|scope| {
+            let workers: Vec<_> = paths
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    let loader = &*self.loader;
+                    scope.spawn(move || {
+                        chunk
+                            .iter()
+                            .map(|path| Self::open(loader, path.as_os_str()))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+
+            workers
+                .into_iter()
+                .flat_map(|worker| {
+                    worker
+                        .join()
+                        .unwrap_or_else(|cause| panic::resume_unwind(cause))
+                })
+                .collect()
+        });
+
+        self.activate_opened(paths.into_iter().zip(opened), &mut report)?;
+
+        Ok(report)
+    }
+
+    /// Construct the plugins in each library opened from a folder (on another thread, so the
+    /// failures to open them are only reported here), and then activate them all in dependency
+    /// order, recording the outcome of each in `report`.
+    pub(crate) unsafe fn activate_opened(
+        &mut self,
+        opened: impl IntoIterator<Item = (PathBuf, Result<Box<dyn LoadedLibrary>>)>,
+        report: &mut LoadReport,
+    ) -> Result<()> {
+        let mut candidates = Vec::new();
+        for (path, library) in opened {
+            match library.and_then(|library| {
+                Self::instantiate(
+                    library,
+                    &path,
+                    &self.constructor_symbol,
+                    true,
+                    self.host_version,
+                    None,
+                    &self.logger,
+                )
+            }) {
+                Ok(candidate) => candidates.push(candidate),
+                Err(e) => {
+                    debug!(self.logger, "Failed to load plugin from {:?}: {}", path, e);
+                    report.failed.push((path, e));
+                }
+            }
+        }
+
+        self.activate_all(candidates, report)
+    }
+
+    /// Load plugins from every folder listed in the environment variable `var`, separated like
+    /// `PATH` is on this platform (by `:` on Unix and `;` on Windows), e.g.
+    /// `PLUGIN_PATH=/usr/lib/app/plugins:~/.app/plugins`.
+    ///
+    /// Each folder is loaded in turn like [`PluginManager::load_plugins`], so the plugins in a
+    /// folder may depend on the ones in the folders listed before it. Folders which don't exist
+    /// are skipped, and nothing is loaded if the variable isn't set. Every other failure,
+    /// including a folder which can't be read or whose plugins' dependencies form a cycle, is
+    /// recorded in the returned [`LoadReport`], and the remaining folders are still loaded.
+    ///
+    /// Since earlier folders are loaded first, a plugin in a later folder with the same name as
+    /// one already loaded is rejected as a duplicate, and shows up in the report's failures. If
+    /// duplicate names are [allowed](PluginManager::set_allow_duplicate_names), both are loaded,
+    /// and lookups by name find the one from the earlier folder.
+    ///
+    /// # Safety
+    ///
+    /// Every candidate is loaded as if by [`PluginManager::load_plugin`], so the same requirements
+    /// apply to each library in every folder.
+    pub unsafe fn load_from_env(&mut self, var: &str) -> LoadReport {
+        let mut report = LoadReport::default();
+        let search_path = match env::var_os(var) {
+            Some(search_path) => search_path,
+            None => {
+                debug!(
+                    self.logger,
+                    "{} isn't set, so there are no plugins to load from it", var
+                );
+                return report;
+            }
+        };
+
+        for dir in env::split_paths(&search_path) {
+            if !dir.is_dir() {
+                debug!(
+                    self.logger,
+                    "Skipping {:?} from {}, since it isn't a folder", dir, var
+                );
+                continue;
+            }
+
+            let mut paths = Vec::new();
+            let scanned = Self::scan_dir(&dir, None, &mut paths, &mut report, &self.logger);
+            skip_loaded(self.plugin_paths(), &mut paths, &self.logger);
+            if let Err(e) = scanned.and_then(|()| self.load_all(paths, &mut report)) {
+                debug!(self.logger, "Failed to load plugins from {:?}: {}", dir, e);
+                report.failed.push((dir, e));
+            }
+        }
+
+        report
+    }
+
+    /// Collect the path of every plugin library in `dir`, recording any directories which can't
+    /// be read in `report`. When `visited` is provided, subdirectories are walked as well, and the
+    /// set is used to avoid visiting the same directory twice.
+    pub(crate) fn scan_dir(
+        dir: &Path,
+        mut visited: Option<&mut HashSet<PathBuf>>,
+        paths: &mut Vec<PathBuf>,
+        report: &mut LoadReport,
+        logger: &Logger,
+    ) -> Result<()> {
+        if let Some(visited) = visited.as_deref_mut() {
+            let canonical =
+                fs::canonicalize(dir).chain_err(|| ErrorKind::DirectoryUnreadable(dir.into()))?;
+            if !visited.insert(canonical) {
+                trace!(logger, "Already visited {:?}, skipping", dir);
+                return Ok(());
+            }
+        }
+
+        let entries = fs::read_dir(dir).chain_err(|| ErrorKind::DirectoryUnreadable(dir.into()))?;
+
+        for entry in entries {
+            let path = entry
+                .chain_err(|| ErrorKind::DirectoryUnreadable(dir.into()))?
+                .path();
+
+            if path.is_dir() {
+                if let Some(visited) = visited.as_deref_mut() {
+                    if let Err(e) = Self::scan_dir(&path, Some(visited), paths, report, logger) {
+                        debug!(logger, "Failed to scan {:?}: {}", path, e);
+                        report.failed.push((path, e));
+                    }
+                    continue;
+                }
+            }
+
+            if !path.is_file() || !is_plugin_library(&path) {
+                trace!(logger, "Skipping {:?}", path);
+                continue;
+            }
+
+            paths.push(path);
+        }
+
+        Ok(())
+    }
+
+    /// Construct the plugin in each of `paths` and then activate them all in dependency order,
+    /// recording the outcome of each in `report`.
+    unsafe fn load_all(&mut self, paths: Vec<PathBuf>, report: &mut LoadReport) -> Result<()> {
+        let mut candidates = Vec::new();
+        for path in paths {
+            match self.prepare(path.as_os_str()) {
+                Ok(candidate) => candidates.push(candidate),
+                Err(e) => {
+                    debug!(self.logger, "Failed to load plugin from {:?}: {}", path, e);
+                    report.failed.push((path, e));
+                }
+            }
+        }
+
+        self.activate_all(candidates, report)
+    }
+
+    /// Construct the plugins in a library the way [`PluginManager::load_library`] does, without
+    /// activating them yet.
+    pub(crate) unsafe fn prepare(&self, filename: &OsStr) -> Result<Candidate> {
+        Self::construct(
+            &*self.loader,
+            filename,
+            &self.constructor_symbol,
+            true,
+            self.host_version,
+            None,
+            &self.logger,
+        )
+    }
+
+    /// Open a plugin library, check it is compatible with the host, and construct its plugins.
+    /// When a `config` is given and the library exports `_plugin_create_with_config`, that
+    /// constructor is used. Otherwise, when `bundle` is set and the library exports
+    /// `_plugin_create_all` or `_plugin_create_v2` (which is given `host_version`), that one is,
+    /// falling back to the constructor exported as `symbol`.
+    /// Nothing is registered with the manager yet, and the library is dropped again on any
+    /// failure, so failed loads can't accumulate dead libraries.
+    unsafe fn construct(
+        loader: &dyn LibraryLoader,
+        filename: &OsStr,
+        symbol: &[u8],
+        bundle: bool,
+        host_version: u32,
+        config: Option<&PluginConfig>,
+        logger: &Logger,
+    ) -> Result<Candidate> {
+        let library = Self::open(loader, filename)?;
+        Self::instantiate(
+            library,
+            Path::new(filename),
+            symbol,
+            bundle,
+            host_version,
+            config,
+            logger,
+        )
+    }
+
+    /// Open a plugin library and check it was built against a compatible version of this crate.
+    /// Other than the initialisers run by the dynamic linker, nothing in the library is executed,
+    /// so this can be done from any thread.
+    pub(crate) unsafe fn open(
+        loader: &dyn LibraryLoader,
+        filename: &OsStr,
+    ) -> Result<Box<dyn LoadedLibrary>> {
+        #[cfg(feature = "wasm")]
+        if crate::wasm::is_wasm(Path::new(filename)) {
+            return crate::wasm::open(Path::new(filename));
+        }
+
+        let library = loader.load(filename)?;
+
+        // Check the plugin was built against a compatible version of this crate before touching
+        // anything else in it, since a mismatched vtable layout would be undefined behaviour.
+        let abi_version = *(library.symbol(b"_plugin_abi_version")? as *const u32);
+        if abi_version != CORE_ABI_VERSION {
+            bail!(ErrorKind::AbiMismatch(abi_version, CORE_ABI_VERSION));
+        }
+        #[cfg(feature = "semver")]
+        crate::semver_check::check(&*library, Path::new(filename))?;
+
+        Ok(library)
+    }
+
+    /// Construct the plugins inside an opened library, as described by
+    /// [`PluginManager::construct`].
+    unsafe fn instantiate(
+        library: Box<dyn LoadedLibrary>,
+        path: &Path,
+        symbol: &[u8],
+        bundle: bool,
+        host_version: u32,
+        config: Option<&PluginConfig>,
+        logger: &Logger,
+    ) -> Result<Candidate> {
+        // See the ABI contract documented on `declare_plugin!`. A `dyn Plugin` pointer has no C
+        // equivalent, but both sides are built by the same compiler, so it only has to agree with
+        // itself.
+        #[allow(improper_ctypes_definitions)]
+        type PluginCreate = unsafe extern "C-unwind" fn() -> *mut dyn Plugin;
+        #[allow(improper_ctypes_definitions)]
+        type PluginCreateAll = unsafe extern "C-unwind" fn(PluginRegistrar, *mut c_void);
+        #[allow(improper_ctypes_definitions)]
+        type PluginCreateWithConfig = unsafe extern "C-unwind" fn(&PluginConfig) -> *mut dyn Plugin;
+        #[allow(improper_ctypes_definitions)]
+        type PluginCreateV2 = unsafe extern "C-unwind" fn(u32) -> *mut dyn Plugin;
+
+        let with_config = config.and_then(|config| {
+            library.symbol(CONFIG_CONSTRUCTOR).ok().map(|address| {
+                (
+                    mem::transmute::<*const c_void, PluginCreateWithConfig>(address),
+                    config,
+                )
+            })
+        });
+        let create_all = match bundle {
+            true => library
+                .symbol(BUNDLE_CONSTRUCTOR)
+                .ok()
+                .map(|address| mem::transmute::<*const c_void, PluginCreateAll>(address)),
+            false => None,
+        };
+        let versioned = match bundle {
+            true => library
+                .symbol(VERSIONED_CONSTRUCTOR)
+                .ok()
+                .map(|address| mem::transmute::<*const c_void, PluginCreateV2>(address)),
+            false => None,
+        };
+
+        #[cfg(feature = "wasm")]
+        let wasm = crate::wasm::instantiate(&*library, path).transpose()?;
+        #[cfg(not(feature = "wasm"))]
+        let wasm = None;
+
+        let created = match (wasm, with_config, create_all, versioned) {
+            (Some(plugin), ..) => Ok(vec![plugin]),
+            (None, Some((constructor, config)), _, _) => {
+                match panic::catch_unwind(|| constructor(config)) {
+                    Ok(plugin) if plugin.is_null() => {
+                        bail!(ErrorKind::ConstructorReturnedNull(path.to_owned()))
+                    }
+                    Ok(plugin) => Ok(vec![Box::from_raw(plugin)]),
+                    Err(cause) => Err(cause),
+                }
+            }
+            (None, None, Some(constructor), _) => {
+                let mut created: Vec<*mut dyn Plugin> = Vec::new();
+                let registry = &mut created as *mut Vec<*mut dyn Plugin> as *mut c_void;
+                let outcome = panic::catch_unwind(|| constructor(collect_plugin, registry));
+                let returned_null = created.iter().any(|plugin| plugin.is_null());
+                let plugins: Vec<Box<dyn Plugin>> = created
+                    .into_iter()
+                    .filter(|plugin| !plugin.is_null())
+                    .map(|plugin| Box::from_raw(plugin))
+                    .collect();
+                match outcome {
+                    Ok(()) if returned_null => {
+                        drop(plugins);
+                        bail!(ErrorKind::ConstructorReturnedNull(path.to_owned()))
+                    }
+                    Ok(()) => Ok(plugins),
+                    Err(cause) => {
+                        drop(plugins);
+                        Err(cause)
+                    }
+                }
+            }
+            (None, None, None, Some(constructor)) => {
+                match panic::catch_unwind(|| constructor(host_version)) {
+                    Ok(plugin) if plugin.is_null() => {
+                        bail!(ErrorKind::IncompatibleHost(path.to_owned(), host_version))
+                    }
+                    Ok(plugin) => Ok(vec![Box::from_raw(plugin)]),
+                    Err(cause) => Err(cause),
+                }
+            }
+            (None, None, None, None) => {
+                let constructor: PluginCreate = mem::transmute(library.symbol(symbol)?);
+                match panic::catch_unwind(|| constructor()) {
+                    Ok(plugin) if plugin.is_null() => {
+                        bail!(ErrorKind::ConstructorReturnedNull(path.to_owned()))
+                    }
+                    Ok(plugin) => Ok(vec![Box::from_raw(plugin)]),
+                    Err(cause) => Err(cause),
+                }
+            }
+        };
+        let plugins = match created {
+            Ok(plugins) => plugins,
+            Err(cause) => bail!(ErrorKind::ConstructorPanicked(
+                panic_message(&cause).to_owned()
+            )),
+        };
+
+        if plugins.is_empty() {
+            drop(plugins);
+            bail!(ErrorKind::NoPlugins(path.to_owned()));
+        }
+        for plugin in &plugins {
+            debug!(
+                logger,
+                target: &log_target(plugin.name()),
+                "Loaded Plugin: {} (version {}) from {:?}",
+                plugin.name(),
+                plugin.version(),
+                path
+            );
+        }
+
+        Ok(Candidate {
+            plugins,
+            library: Some(library),
+            path: path.to_owned(),
+        })
+    }
+
+    /// Fire the `on_plugin_load()` of each plugin constructed from a library and, if they all
+    /// succeed, register the plugins along with their library. On failure everything is dropped,
+    /// and the manager is put back the way it was (see [`PluginManager::try_load_plugin`]).
+    pub(crate) fn activate(&mut self, candidate: Candidate) -> Result<Vec<PluginId>> {
+        let next_id = self.next_id;
+        let names = candidate.names();
+        let timings: Vec<(String, Option<PluginTimings>)> = names
+            .iter()
+            .map(|name| (name.clone(), self.timings.get(name).cloned()))
+            .collect();
+
+        let ids = self.admit(&candidate, &names)?;
+        let outcomes = fire_load_all(
+            &candidate.plugins,
+            &names,
+            &mut self.context,
+            &ids,
+            self.capture_output,
+            &self.logger,
+        );
+        let result = self.settle(candidate, names, ids, outcomes);
+
+        if result.is_err() {
+            // Nothing which failed to load is left to use the ids it was given, so they can be
+            // handed out again.
+            self.next_id = next_id;
+            for (name, timings) in timings {
+                match timings {
+                    Some(timings) => self.timings.insert(name, timings),
+                    None => self.timings.remove(&name),
+                };
+            }
+        }
+        result
+    }
+
+    /// Check the plugins constructed from a library (going by `names`) may be registered, and hand
+    /// out their ids.
+    fn admit(&mut self, candidate: &Candidate, names: &[String]) -> Result<Vec<PluginId>> {
+        for name in names {
+            let name = name.as_str();
+            let rejection = if self.denylist.contains(name) {
+                Some("it is on the denylist")
+            } else if self
+                .allowlist
+                .as_ref()
+                .is_some_and(|allowed| !allowed.contains(name))
+            {
+                Some("it isn't on the allowlist")
+            } else {
+                None
+            };
+
+            if let Some(reason) = rejection {
+                warn!(
+                    self.logger,
+                    target: &log_target(name),
+                    "Rejecting the plugin {:?} from {:?}, since {}", name, candidate.path, reason
+                );
+                bail!(ErrorKind::PluginRejected(name.to_owned()));
+            }
+        }
+
+        // Plugins from the same library are loaded together, so they may depend on each other.
+        for (plugin, name) in candidate.plugins.iter().zip(names) {
+            for needs in plugin.dependencies() {
+                if !self.is_loaded(&needs) && !names.contains(&needs) {
+                    bail!(ErrorKind::MissingDependency(name.clone(), needs));
+                }
+            }
+        }
+
+        if !self.allow_duplicate_names {
+            for (index, name) in names.iter().enumerate() {
+                if self.is_loaded(name) || names[..index].contains(name) {
+                    bail!(ErrorKind::DuplicatePlugin(name.clone()));
+                }
+            }
+        }
+
+        for hook in &self.admission_hooks {
+            for plugin in &candidate.plugins {
+                hook(plugin.as_ref(), candidate.library.as_deref())?;
+            }
+        }
+
+        let ids = candidate
+            .plugins
+            .iter()
+            .map(|_| {
+                self.next_id += 1;
+                PluginId(self.next_id - 1)
+            })
+            .collect();
+        Ok(ids)
+    }
+
+    /// Register the plugins from a library (going by `names`) whose `on_plugin_load()` has been
+    /// fired, given the outcome of each (see [`fire_load_all`]). If any of them failed, the plugins
+    /// registered before it are unloaded again, and everything else is thrown away along with the
+    /// library.
+    fn settle(
+        &mut self,
+        candidate: Candidate,
+        names: Vec<String>,
+        ids: Vec<PluginId>,
+        outcomes: Vec<LoadOutcome>,
+    ) -> Result<Vec<PluginId>> {
+        let Candidate {
+            plugins,
+            library,
+            path,
+        } = candidate;
+
+        // We need to keep the library around, otherwise our plugins' vtables will point to
+        // garbage. It is pushed up front so the library's plugins can share it, and released
+        // again like any other library once none of them are left.
+        let library = library.map(|library| {
+            self.loaded_libraries.push(library);
+            self.library_paths.push(path.clone());
+            self.loaded_libraries.len() - 1
+        });
+
+        let mut plugins = plugins.into_iter();
+        let mut loaded = Vec::new();
+        let mut names = names.into_iter();
+        for ((outcome, elapsed), id) in outcomes.into_iter().zip(ids) {
+            let plugin = plugins.next().unwrap();
+            let name = names.next().unwrap();
+
+            let failure = match outcome {
+                Ok(Ok(())) => None,
+                Ok(Err(e)) => Some(ErrorKind::PluginLoadFailed(name.clone(), describe(&e))),
+                Err(cause) if self.panic_policy == PanicPolicy::Propagate => {
+                    if let Some(hook) = &self.on_load {
+                        let kind = ErrorKind::PluginPanicked(
+                            name.clone(),
+                            panic_message(&cause).to_owned(),
+                        );
+                        hook(&plugin.metadata(), elapsed, &Err(kind.into()));
+                    }
+
+                    // The panic's payload may have been created by the plugin, so its library has
+                    // to outlive it. Keep the library around until the manager is unloaded.
+                    self.context.remove_owned_by(id);
+                    drop(plugin);
+                    drop(plugins);
+                    panic::resume_unwind(cause);
+                }
+                Err(cause) => Some(ErrorKind::PluginPanicked(
+                    name.clone(),
+                    panic_message(&cause).to_owned(),
+                )),
+            };
+
+            let result = match failure {
+                Some(kind) => Err(Error::from(kind)),
+                None => Ok(()),
+            };
+            let over_budget = record_timing(
+                &mut self.timings,
+                self.limits,
+                &self.logger,
+                &name,
+                "on_plugin_load",
+                elapsed,
+            );
+            if let Some(hook) = &self.on_load {
+                hook(&plugin.metadata(), elapsed, &result);
+            }
+
+            if let Err(e) = result {
+                // The error is built from owned copies before the library goes away, since
+                // everything the plugin handed us (including its name and any error it returned)
+                // may live inside it.
+                self.context.remove_owned_by(id);
+                drop(plugin);
+                drop(plugins);
+
+                // Unloading the last of the siblings releases the library.
+                for &id in loaded.iter().rev() {
+                    if let Err(e) = self.unload_by_id(id) {
+                        warn!(self.logger, "{}", e);
+                    }
+                }
+                if let (true, Some(library)) = (loaded.is_empty(), library) {
+                    self.release_library(library);
+                }
+                return Err(e);
+            }
+
+            let poisoned = over_budget && self.limits.poisons();
+            if poisoned {
+                error!(
+                    self.logger,
+                    target: &log_target(&name),
+                    "The plugin `{}` overran its time limit while loading, and won't be called again",
+                    name
+                );
+            }
+            self.subscribe(id, plugin.subscriptions());
+            let commands = plugin.commands();
+            let modified = fs::metadata(&path).and_then(|m| m.modified()).ok();
+            self.plugins.push(plugin);
+            self.records.push(PluginRecord {
+                id,
+                name,
+                library,
+                path: path.clone(),
+                source: None,
+                poisoned,
+                enabled: true,
+                group: None,
+                modified,
+                commands,
+            });
+            loaded.push(id);
+        }
+
+        Ok(loaded)
+    }
+
+    /// Activate a batch of constructed libraries in dependency order, recording the outcome of
+    /// each in `report`.
+    fn activate_all(&mut self, candidates: Vec<Candidate>, report: &mut LoadReport) -> Result<()> {
+        let order = dependency_order(&candidates)?;
+        let mut candidates: Vec<_> = candidates.into_iter().map(Some).collect();
+
+        for index in order {
+            let candidate = candidates[index].take().unwrap();
+            let path = candidate.path.clone();
+
+            match self.activate(candidate) {
+                Ok(ids) => {
+                    report.plugins.extend(self.names_of(&ids));
+                    report.loaded.push(path);
+                }
+                Err(e) => {
+                    debug!(self.logger, "Failed to load plugin from {:?}: {}", path, e);
+                    report.failed.push((path, e));
+                }
+            }
+        }
+
+        Ok(())
     }
 
     /// Unload all plugins and loaded plugin libraries, making sure to fire their `on_plugin_unload()`
     /// methods so they can do any necessary cleanup.
-    pub fn unload(&mut self) {
-        debug!("Unloading plugins");
+    ///
+    /// Plugins are unloaded in the reverse of their [dependency](Plugin::dependencies) order, so
+    /// a plugin is always unloaded before the plugins it depends on, whichever order they were
+    /// loaded in. Otherwise plugins with a lower [shutdown priority](Plugin::shutdown_priority)
+    /// are unloaded first, and among plugins with the same priority, the last plugin loaded is the
+    /// first to have its `on_plugin_unload()` fired.
+    /// Libraries are dropped in the reverse of the order they were loaded in. If the dependencies
+    /// somehow form a cycle, a warning is logged and every plugin is unloaded in reverse load
+    /// order (still going by priority) instead.
+    ///
+    /// Every plugin is unloaded even if some of them fail: a plugin returning an error from (or
+    /// panicking in) `on_plugin_unload()` is recorded, and the remaining plugins are still
+    /// unloaded, so every library always gets released. The failures are returned along with the
+    /// name of the plugin responsible.
+    pub fn unload(&mut self) -> std::result::Result<(), Vec<(String, Error)>> {
+        debug!(self.logger, "Unloading plugins");
+
+        let mut failures = Vec::new();
+        drop(self.take_unloaded_plugins(&mut failures));
+        self.release_all_libraries(&HashSet::new());
 
-        for plugin in self.plugins.drain(..) {
-            trace!("Firing on_plugin_unload for {:?}", plugin.name());
-            plugin.on_plugin_unload();
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(failures)
         }
+    }
 
-        for lib in self.loaded_libraries.drain(..) {
-            drop(lib);
+    /// Unload every plugin like [`PluginManager::unload`], and then forget everything the manager
+    /// has picked up since it was created, so it can be reused as if it were new: the plugins'
+    /// [timings](PluginManager::timings) are cleared, messages they sent which haven't been
+    /// [delivered](PluginManager::deliver_messages) yet are dropped and, with the `watch` feature,
+    /// every folder stops being watched. Any failures to unload are returned as from
+    /// [`PluginManager::unload`].
+    ///
+    /// The manager's configuration is kept, i.e. everything set through the
+    /// [`PluginManagerBuilder`] or the manager's own setters, hooks and logger, along with the
+    /// services the host registered in the [`PluginContext`]. [`PluginId`]s aren't handed out
+    /// again, so an id left over from before the reset can't refer to a new plugin.
+    pub fn reset(&mut self) -> std::result::Result<(), Vec<(String, Error)>> {
+        let unloaded = self.unload();
+        self.timings.clear();
+        self.context.clear_messages();
+        #[cfg(feature = "watch")]
+        {
+            self.watcher = None;
         }
+        unloaded
     }
-}
 
-/// We implement `Drop` for PluginManager, so that plugins are always unloaded when the `PluginManager`
-/// gets dropped. This gives them a chance to do any necessary cleanup.
-impl Drop for PluginManager {
-    fn drop(&mut self) {
-        if !self.plugins.is_empty() || !self.loaded_libraries.is_empty() {
-            self.unload();
+    /// Unload every plugin like [`PluginManager::unload`], firing their `on_plugin_unload()` in
+    /// the same order, but hand the plugins back instead of dropping them, e.g. to look at their
+    /// final state after shutdown.
+    ///
+    /// The plugins come with the libraries they were loaded from, which are kept alive for as long
+    /// as the returned [`DrainedPlugins`] is, since dropping a library would leave its plugins
+    /// pointing at code which is no longer there. The manager is left empty, and can go on to load
+    /// other plugins. A plugin failing to unload is logged, and still handed back.
+    pub fn drain(&mut self) -> DrainedPlugins {
+        debug!(self.logger, "Draining plugins");
+
+        let mut failures = Vec::new();
+        let plugins = self.take_unloaded_plugins(&mut failures);
+        for (name, e) in failures {
+            warn!(
+                self.logger,
+                target: &log_target(&name),
+                "Failed to unload the plugin `{}`: {}",
+                name,
+                e
+            );
+        }
+
+        // Services registered by the plugins would outlive their libraries in the context.
+        self.context.remove_plugin_services();
+        self.library_paths.clear();
+        self.records.clear();
+        self.unsubscribe_all();
+        DrainedPlugins {
+            plugins,
+            libraries: mem::take(&mut self.loaded_libraries),
+        }
+    }
+
+    /// Take every plugin out of the manager, in load order, firing their `on_plugin_unload()` in
+    /// shutdown order and adding any failures to `failures`. Their libraries are left alone.
+    fn take_unloaded_plugins(
+        &mut self,
+        failures: &mut Vec<(String, Error)>,
+    ) -> Vec<Box<dyn Plugin>> {
+        // Dependents are torn down first, so a plugin can still use the plugins it depends on
+        // while it cleans up.
+        let order = self.shutdown_order();
+        let plugins = mem::take(&mut self.plugins);
+        for index in order {
+            let plugin = &plugins[index];
+            let name = self.records[index].name.clone();
+            trace!(
+                self.logger,
+                target: &log_target(&name),
+                "Firing on_plugin_unload for {:?}",
+                name
+            );
+            let fired = panic::catch_unwind(AssertUnwindSafe(|| {
+                fire_unload(
+                    plugin.as_ref(),
+                    &name,
+                    self.panic_policy,
+                    self.on_unload.as_ref(),
+                    self.capture_output,
+                    &self.logger,
+                )
+            }));
+            let (outcome, elapsed) = match fired {
+                Ok(fired) => fired,
+                Err(cause) => {
+                    // A panic let through under `PanicPolicy::Propagate` would otherwise leave
+                    // every library (and the services plugins registered) behind in the manager,
+                    // so this makes sure they are released first, as a scope guard would. The
+                    // panic's payload may have been created by the plugin, so its library has to
+                    // outlive it.
+                    drop(plugins);
+                    let leaked = self.records[index].library.into_iter().collect();
+                    self.release_all_libraries(&leaked);
+                    panic::resume_unwind(cause);
+                }
+            };
+            record_timing(
+                &mut self.timings,
+                self.limits,
+                &self.logger,
+                &name,
+                "on_plugin_unload",
+                elapsed,
+            );
+            if let Err(e) = outcome {
+                failures.push((name, e));
+            }
         }
+        plugins
+    }
+
+    /// Work out an order in which to unload every plugin so that each comes before the plugins it
+    /// depends on, returned as indices into `plugins`. Otherwise, the plugins with the lowest
+    /// shutdown priority come first, and then the ones loaded last.
+    fn shutdown_order(&self) -> Vec<usize> {
+        let names: Vec<String> = self.plugin_names().map(str::to_owned).collect();
+
+        let mut preferred: Vec<usize> = (0..self.plugins.len()).rev().collect();
+        preferred.sort_by_key(|&index| self.plugins[index].shutdown_priority());
+        let mut rank = vec![0; preferred.len()];
+        for (position, &index) in preferred.iter().enumerate() {
+            rank[index] = position;
+        }
+
+        // A plugin has to be unloaded after every plugin depending on it.
+        let mut dependents: Vec<Vec<usize>> = vec![Vec::new(); names.len()];
+        for (index, plugin) in self.plugins.iter().enumerate() {
+            for dep in plugin.dependencies() {
+                match names.iter().position(|name| *name == dep) {
+                    Some(dep) if dep != index => dependents[dep].push(index),
+                    _ => {}
+                }
+            }
+        }
+        for dependents in &mut dependents {
+            dependents.sort_by_key(|&index| rank[index]);
+        }
+
+        match topological_order(&names, &dependents, &preferred) {
+            Ok(order) => order,
+            Err(e) => {
+                warn!(
+                    self.logger,
+                    "{}, so plugins are unloaded in reverse load order instead", e
+                );
+                preferred
+            }
+        }
+    }
+
+    /// Unload all plugins like [`PluginManager::unload`], but give each plugin's
+    /// `on_plugin_unload()` at most `timeout` to finish, so a plugin which hangs while cleaning up
+    /// can't hang the host's shutdown along with it. A plugin which takes too long is reported
+    /// with an `UnloadTimeout` error, and the manager moves on to the next one.
+    ///
+    /// Each `on_plugin_unload()` is fired on a separate thread, which can't be stopped once it is
+    /// running. The library of a plugin still inside `on_plugin_unload()` is deliberately leaked
+    /// (along with any other plugins it holds), since the plugin's code keeps running and
+    /// dropping the library from under it would be undefined behaviour. Every other library is
+    /// dropped as usual.
+    ///
+    /// Plugins have to be safe to unload from a thread other than the caller's, which is already
+    /// required of them by `Plugin: Send`.
+    pub fn unload_with_timeout(
+        &mut self,
+        timeout: Duration,
+    ) -> std::result::Result<(), Vec<(String, Error)>> {
+        debug!(
+            self.logger,
+            "Unloading plugins, giving each of them {:?}", timeout
+        );
+
+        let mut failures = Vec::new();
+        let mut leaked = HashSet::new();
+        // Reversed, since plugins are popped off the end.
+        let order = self.shutdown_order();
+        let mut slots: Vec<_> = self
+            .plugins
+            .drain(..)
+            .zip(self.records.drain(..))
+            .map(Some)
+            .collect();
+        let mut pending: Vec<_> = order
+            .into_iter()
+            .rev()
+            .map(|index| slots[index].take().unwrap())
+            .collect();
+        while let Some((plugin, record)) = pending.pop() {
+            let name = record.name.clone();
+            trace!(self.logger, target: &log_target(&name), "Firing on_plugin_unload for {:?}", name);
+
+            let (sender, receiver) = mpsc::channel();
+            let policy = self.panic_policy;
+            let hook = self.on_unload.clone();
+            let capture = self.capture_output;
+            let logger = self.logger.clone();
+            let thread_name = name.clone();
+            let worker = thread::spawn(move || {
+                let outcome = fire_unload(
+                    plugin.as_ref(),
+                    &thread_name,
+                    policy,
+                    hook.as_ref(),
+                    capture,
+                    &logger,
+                );
+                // The plugin has to go before its library does, so drop it before reporting back.
+                drop(plugin);
+                let _ = sender.send(outcome);
+            });
+
+            match receiver.recv_timeout(timeout) {
+                Ok((outcome, elapsed)) => {
+                    record_timing(
+                        &mut self.timings,
+                        self.limits,
+                        &self.logger,
+                        &name,
+                        "on_plugin_unload",
+                        elapsed,
+                    );
+                    if let Err(e) = outcome {
+                        failures.push((name, e));
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => {
+                    warn!(
+                        self.logger,
+                        target: &log_target(&name),
+                        "The plugin {:?} is stuck in on_plugin_unload, leaking its library", name
+                    );
+                    leaked.extend(record.library);
+                    failures.push((name.clone(), ErrorKind::UnloadTimeout(name, timeout).into()));
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    // The panic is propagated, as `PanicPolicy::Propagate` asks. Its payload may
+                    // have been created by the plugin, so its library has to outlive it.
+                    let cause = worker.join().unwrap_err();
+                    leaked.extend(record.library);
+                    drop(pending);
+                    self.release_all_libraries(&leaked);
+                    panic::resume_unwind(cause);
+                }
+            }
+        }
+
+        self.release_all_libraries(&leaked);
+
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(failures)
+        }
+    }
+
+    /// Unload a single plugin by name, firing its `on_plugin_unload()` method and releasing the
+    /// library it was loaded from. Other plugins are left untouched.
+    ///
+    /// A library exporting several plugins is shared between them, and only released along with
+    /// the last of them, since the others' vtables still point into it. Until then it stays
+    /// loaded, and is counted by [`PluginManager::library_plugin_counts`].
+    ///
+    /// If the plugin's `on_plugin_unload()` fails, the plugin is still unloaded, and the error is
+    /// returned.
+    pub fn unload_plugin(&mut self, name: &str) -> Result<()> {
+        let index = self.position(name)?;
+        self.unload_at(index)
+    }
+
+    /// Unload every plugin going by one of `names` in a single pass, in the reverse of the order
+    /// they were loaded in, like [`PluginManager::unload_group`]. Each library is released once
+    /// none of the plugins left need it. Other plugins are left untouched.
+    ///
+    /// Every named plugin is unloaded even if some of them fail, and a name which doesn't belong
+    /// to any loaded plugin is reported with a `PluginNotFound` error without holding up the
+    /// rest. The failures are returned along with the name they are about.
+    pub fn unload_plugins(
+        &mut self,
+        names: &[&str],
+    ) -> std::result::Result<(), Vec<(String, Error)>> {
+        let wanted: HashSet<&str> = names.iter().copied().collect();
+        let mut missing = HashSet::new();
+        let mut failures: Vec<(String, Error)> = names
+            .iter()
+            .filter(|&&name| !self.is_loaded(name) && missing.insert(name))
+            .map(|&name| {
+                (
+                    name.to_owned(),
+                    ErrorKind::PluginNotFound(name.to_owned()).into(),
+                )
+            })
+            .collect();
+
+        for index in (0..self.plugins.len()).rev() {
+            if !wanted.contains(self.records[index].name.as_str()) {
+                continue;
+            }
+            let name = self.records[index].name.clone();
+            if let Err(e) = self.unload_at(index) {
+                failures.push((name, e));
+            }
+        }
+
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(failures)
+        }
+    }
+
+    /// Unload every plugin in a `group` (see [`PluginManager::load_plugin_in_group`]), in the
+    /// reverse of the order they were loaded in, releasing each library once none of the plugins
+    /// left need it. Plugins outside the group are left untouched.
+    ///
+    /// As with [`PluginManager::unload`], every member is unloaded even if some of them fail, and
+    /// the failures are returned along with the name of the plugin responsible. Unloading a group
+    /// with no plugins in it does nothing.
+    pub fn unload_group(&mut self, group: &str) -> std::result::Result<(), Vec<(String, Error)>> {
+        debug!(self.logger, "Unloading the plugin group {:?}", group);
+
+        let mut failures = Vec::new();
+        for index in (0..self.records.len()).rev() {
+            if self.records[index].group.as_deref() != Some(group) {
+                continue;
+            }
+            let name = self.records[index].name.clone();
+            if let Err(e) = self.unload_at(index) {
+                failures.push((name, e));
+            }
+        }
+
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(failures)
+        }
+    }
+
+    /// Replace a loaded plugin with a freshly loaded copy from `filename`, without restarting the
+    /// host. The old instance has its `on_plugin_unload()` fired and its library dropped, then the
+    /// new library goes through the normal [`PluginManager::load_plugin`] path. The new plugin
+    /// takes the old one's position among [`PluginManager::plugins`], and keeps its [`PluginId`].
+    ///
+    /// Any state the old instance returns from [`Plugin::save_state`] is handed to the new one
+    /// through [`Plugin::restore_state`] before its `on_plugin_load()` is fired. If saving the
+    /// state panics, the panic is logged and the state lost (unless the panic is propagated);
+    /// if restoring it panics, the reload fails.
+    ///
+    /// If the plugin came from a library exporting several plugins, its siblings are reloaded
+    /// along with it, since the old library can't be dropped while any of them still use it. The
+    /// new library's plugins are all put in the old plugin's place, and each keeps its id if the
+    /// old library had a plugin with the same name.
+    ///
+    /// If loading the new library fails, the old plugin is already gone; the returned error says
+    /// so, so the gap can be dealt with.
+    ///
+    /// # Safety
+    ///
+    /// The new library is loaded through [`PluginManager::load_plugin`], so the same requirements
+    /// apply.
+    pub unsafe fn reload_plugin<P: AsRef<OsStr>>(&mut self, name: &str, filename: P) -> Result<()> {
+        self.reload_library(name, filename.as_ref()).map(|_| ())
+    }
+
+    /// Reload the plugin called `name` (and its siblings) from `filename`, as described by
+    /// [`PluginManager::reload_plugin`], returning the ids of the plugins loaded in their place.
+    unsafe fn reload_library(&mut self, name: &str, filename: &OsStr) -> Result<Vec<PluginId>> {
+        let index = self.position(name)?;
+        let siblings: Vec<usize> = match self.records[index].library {
+            Some(library) => (0..self.records.len())
+                .filter(|&i| self.records[i].library == Some(library))
+                .collect(),
+            None => vec![index],
+        };
+        let ids: HashMap<String, PluginId> = siblings
+            .iter()
+            .map(|&i| (self.records[i].name.clone(), self.records[i].id))
+            .collect();
+        let group = self.records[index].group.clone();
+        let states: HashMap<String, Vec<u8>> = siblings
+            .iter()
+            .filter_map(|&i| {
+                let name = &self.records[i].name;
+                save_state(
+                    self.plugins[i].as_ref(),
+                    name,
+                    self.panic_policy,
+                    &self.logger,
+                )
+                .map(|state| (name.clone(), state))
+            })
+            .collect();
+
+        for &index in siblings.iter().rev() {
+            if let Err(e) = self.unload_at(index) {
+                warn!(self.logger, "{}", e);
+            }
+        }
+
+        let loaded = self
+            .load_restoring(filename, &states)
+            .chain_err(|| ErrorKind::ReloadFailed(name.to_owned()))?;
+
+        // `load_library` always appends, so move the new plugins back into the old ones' place.
+        let start = self.plugins.len() - loaded.len();
+        let plugins: Vec<_> = self.plugins.drain(start..).collect();
+        let records: Vec<_> = self.records.drain(start..).collect();
+        let mut reloaded = Vec::with_capacity(loaded.len());
+        for (offset, (plugin, mut record)) in plugins.into_iter().zip(records).enumerate() {
+            if let Some(&id) = ids.get(&record.name) {
+                self.resubscribe(record.id, id);
+                record.id = id;
+            }
+            record.group = group.clone();
+            reloaded.push(record.id);
+            self.plugins.insert(siblings[0] + offset, plugin);
+            self.records.insert(siblings[0] + offset, record);
+        }
+
+        Ok(reloaded)
+    }
+
+    /// Load a library like [`PluginManager::load_library`], handing each new plugin the state
+    /// saved from the old plugin with the same name before its `on_plugin_load()` is fired.
+    unsafe fn load_restoring(
+        &mut self,
+        filename: &OsStr,
+        states: &HashMap<String, Vec<u8>>,
+    ) -> Result<Vec<PluginId>> {
+        let mut candidate = self.prepare(filename)?;
+
+        for plugin in &mut candidate.plugins {
+            if let Some(state) = states.get(plugin.name()) {
+                restore_state(plugin.as_mut(), state, self.panic_policy, &self.logger)?;
+            }
+        }
+
+        self.activate(candidate)
+    }
+
+    /// Reload every plugin from the file it was loaded from, e.g. after rebuilding all of them.
+    ///
+    /// Every plugin is unloaded first (in reverse load order, as by [`PluginManager::unload`]),
+    /// and then each file is loaded again, in dependency order. A plugin which fails to come back
+    /// doesn't stop the others from reloading; the failures are returned along with the name of
+    /// the plugin responsible. Each reloaded plugin keeps its [`PluginId`], provided its library
+    /// still exports it under the same name. Errors from the old plugins' `on_plugin_unload()` are
+    /// only logged, since the plugins are going away regardless.
+    ///
+    /// Plugins [loaded from memory](PluginManager::load_plugin_from_bytes) or
+    /// [registered](PluginManager::register_plugin) directly have no file to be reloaded from, so
+    /// they are left as they are.
+    ///
+    /// # Safety
+    ///
+    /// Every file is loaded again as if by [`PluginManager::load_plugin`], so the same
+    /// requirements apply.
+    pub unsafe fn reload_all(&mut self) -> std::result::Result<(), Vec<(String, Error)>> {
+        let mut paths = Vec::new();
+        let mut names: HashMap<PathBuf, Vec<String>> = HashMap::new();
+        let mut ids = HashMap::new();
+        for record in &self.records {
+            if !record.has_file() {
+                continue;
+            }
+            if !names.contains_key(&record.path) {
+                paths.push(record.path.clone());
+            }
+            names
+                .entry(record.path.clone())
+                .or_default()
+                .push(record.name.clone());
+            ids.insert(record.name.clone(), (record.id, record.group.clone()));
+        }
+
+        for index in (0..self.records.len()).rev() {
+            if self.records[index].has_file() {
+                if let Err(e) = self.unload_at(index) {
+                    warn!(self.logger, "{}", e);
+                }
+            }
+        }
+
+        let start = self.plugins.len();
+        let mut report = LoadReport::default();
+        if let Err(e) = self.load_all(paths.clone(), &mut report) {
+            // The dependencies form a cycle, so nothing was loaded.
+            let reason = describe(&e);
+            report.failed = paths
+                .into_iter()
+                .map(|path| (path, Error::from(reason.clone())))
+                .collect();
+        }
+
+        for index in start..self.records.len() {
+            if let Some((id, group)) = ids.get(&self.records[index].name) {
+                self.resubscribe(self.records[index].id, *id);
+                self.records[index].id = *id;
+                self.records[index].group = group.clone();
+            }
+        }
+
+        let mut failures = Vec::new();
+        for (path, e) in report.failed {
+            let mut names = names.remove(&path).unwrap_or_default();
+            let last = names.pop().unwrap_or_else(|| path.display().to_string());
+            // Every plugin from the library failed along with it, but there's only one error.
+            for name in names {
+                let cause = Error::from(describe(&e));
+                failures.push((
+                    name.clone(),
+                    Error::with_chain(cause, ErrorKind::ReloadFailed(name)),
+                ));
+            }
+            failures.push((
+                last.clone(),
+                Error::with_chain(e, ErrorKind::ReloadFailed(last)),
+            ));
+        }
+
+        if failures.is_empty() {
+            Ok(())
+        } else {
+            Err(failures)
+        }
+    }
+
+    /// Reload every plugin whose library has changed on disk since it was loaded, going by the
+    /// file's modification time, and return the names of the plugins loaded in their place. This
+    /// is a polling alternative to watching the plugin folders (with the `watch` feature), for
+    /// filesystems which can't be watched (such as network mounts): call it from the host's own
+    /// loop every so often.
+    ///
+    /// Each changed library is reloaded with [`PluginManager::reload_plugin`], so its plugins keep
+    /// their ids and places, and can hand their state over. A file which can't be looked at right
+    /// now (e.g. because it is being replaced) is left for the next call, and plugins with no file
+    /// are never reloaded.
+    ///
+    /// If a library fails to reload, the error is returned straight away, and the remaining
+    /// libraries are left until the next call. The failed library's plugins are gone, as
+    /// described for [`PluginManager::reload_plugin`].
+    ///
+    /// # Safety
+    ///
+    /// Every changed file is loaded again as if by [`PluginManager::load_plugin`], so the same
+    /// requirements apply.
+    pub unsafe fn reload_changed(&mut self) -> Result<Vec<String>> {
+        let mut seen = HashSet::new();
+        let changed: Vec<(String, PathBuf)> = self
+            .records
+            .iter()
+            .filter(|r| r.has_file() && seen.insert(&r.path))
+            .filter(|r| {
+                fs::metadata(&r.path)
+                    .and_then(|m| m.modified())
+                    .is_ok_and(|modified| Some(modified) != r.modified)
+            })
+            .map(|r| (r.name.clone(), r.path.clone()))
+            .collect();
+
+        let mut reloaded = Vec::new();
+        for (name, path) in changed {
+            debug!(self.logger, "{:?} has changed, reloading it", path);
+            let ids = self.reload_library(&name, path.as_os_str())?;
+            reloaded.extend(self.names_of(&ids));
+        }
+        Ok(reloaded)
+    }
+
+    /// Fire every enabled plugin's `update()` callback, in the order they were loaded. This is
+    /// meant to be called once per tick of the host's main loop.
+    ///
+    /// Under [`PanicPolicy::Catch`], a plugin panicking in `update()` is logged and poisoned, and
+    /// the remaining plugins are still updated.
+    pub fn update_all(&mut self, delta: Duration) {
+        self.for_each_plugin("update", None, |plugin| plugin.update(delta));
+        self.deliver_messages();
+    }
+
+    /// Broadcast an event to every enabled plugin's `on_event()` callback, in the order they were
+    /// loaded. Plugins which [subscribed](Plugin::subscriptions) to particular topics only get the
+    /// events with one of those topics.
+    ///
+    /// Under [`PanicPolicy::Catch`], a plugin panicking in `on_event()` is logged and poisoned, and
+    /// the event is still delivered to the remaining plugins.
+    pub fn dispatch_event(&mut self, event: &PluginEvent) {
+        self.for_each_plugin("on_event", Some(&event.topic), |plugin| {
+            plugin.on_event(event)
+        });
+        self.deliver_messages();
+    }
+
+    /// Deliver a message to every enabled plugin's `receive_message()` callback, in the order they
+    /// were loaded, followed by any messages plugins have sent in the meantime (see
+    /// [`PluginManager::deliver_messages`]).
+    ///
+    /// Panics are handled like in [`PluginManager::dispatch_event`].
+    pub fn send_message(&mut self, msg: PluginMessage) {
+        self.deliver(&msg, None);
+        self.deliver_messages();
+    }
+
+    /// Deliver the messages plugins have sent through their
+    /// [`MessageSender`](crate::MessageSender)s, in the order they were sent, each to every enabled
+    /// plugin but the one which sent it. This is how plugins talk to each other: e.g. a plugin
+    /// which collects metrics listens for a `"metrics"` topic, and the plugins producing counters
+    /// send them there.
+    ///
+    /// This happens by itself after [`PluginManager::send_message`], [`PluginManager::update_all`]
+    /// and [`PluginManager::dispatch_event`], so it only has to be called to pass on messages sent
+    /// at other times, e.g. while plugins were being loaded. Messages sent in reply are delivered
+    /// too, but only up to a point, so that plugins which keep replying to each other can't hold
+    /// up the host forever: the rest are left for next time.
+    pub fn deliver_messages(&mut self) {
+        for _ in 0..MAX_DELIVERED_MESSAGES {
+            match self.context.next_message() {
+                Some((sender, msg)) => self.deliver(&msg, sender),
+                None => return,
+            }
+        }
+    }
+
+    /// Deliver `msg` to every enabled plugin but its `sender`.
+    fn deliver(&mut self, msg: &PluginMessage, sender: Option<PluginId>) {
+        for index in 0..self.plugins.len() {
+            let record = &self.records[index];
+            if record.poisoned || !record.enabled || Some(record.id) == sender {
+                continue;
+            }
+            let _ = self.call_at(index, "receive_message", |plugin| {
+                plugin.receive_message(msg)
+            });
+        }
+    }
+
+    /// Call `f` on every plugin in turn, skipping poisoned and disabled plugins. Panics are handled
+    /// according to the manager's [`PanicPolicy`]: when they are caught, the panic is logged
+    /// (tagged with the name of the `hook` being called) and the plugin is poisoned, so that one
+    /// misbehaving plugin can't stop the others being called. If there is a `topic`, only the
+    /// plugins subscribed to it are called.
+    fn for_each_plugin<F: FnMut(&mut dyn Plugin)>(
+        &mut self,
+        hook: &'static str,
+        topic: Option<&str>,
+        mut f: F,
+    ) {
+        let indices = match topic {
+            Some(topic) => self.subscribers(topic),
+            None => (0..self.plugins.len()).collect(),
+        };
+        for index in indices {
+            let record = &self.records[index];
+            if record.poisoned || !record.enabled {
+                continue;
+            }
+            let _ = self.call_at(index, hook, &mut f);
+        }
+    }
+
+    /// The indices of the plugins subscribed to `topic`, in load order.
+    fn subscribers(&self, topic: &str) -> Vec<usize> {
+        let ids = self.topics.get(topic).into_iter().flatten();
+        let mut indices: Vec<usize> = ids
+            .chain(&self.every_topic)
+            .filter_map(|&id| self.records.iter().position(|r| r.id == id))
+            .collect();
+        indices.sort_unstable();
+        indices.dedup();
+        indices
+    }
+
+    /// Index the plugin `id` under each of the `topics` it subscribed to, or under every topic if
+    /// there are none.
+    fn subscribe(&mut self, id: PluginId, topics: Vec<String>) {
+        if topics.is_empty() {
+            self.every_topic.push(id);
+        }
+        for topic in topics {
+            let subscribers = self.topics.entry(topic).or_default();
+            if !subscribers.contains(&id) {
+                subscribers.push(id);
+            }
+        }
+    }
+
+    /// Take the plugin `id` out of the topic index, once it has been unloaded.
+    fn unsubscribe(&mut self, id: PluginId) {
+        self.every_topic.retain(|&other| other != id);
+        self.topics.retain(|_, subscribers| {
+            subscribers.retain(|&other| other != id);
+            !subscribers.is_empty()
+        });
+    }
+
+    /// Empty the topic index, once every plugin has been unloaded.
+    fn unsubscribe_all(&mut self) {
+        self.topics.clear();
+        self.every_topic.clear();
+    }
+
+    /// Move the subscriptions of the plugin `from` over to `to`, when a reloaded plugin takes
+    /// over its predecessor's id.
+    fn resubscribe(&mut self, from: PluginId, to: PluginId) {
+        for id in self
+            .topics
+            .values_mut()
+            .flatten()
+            .chain(&mut self.every_topic)
+        {
+            if *id == from {
+                *id = to;
+            }
+        }
+    }
+
+    /// Call `f` on the plugin at `index`, one of its `hook` callbacks, handling a panic as
+    /// described by [`for_each_plugin`](Self::for_each_plugin). A caught panic's message is
+    /// returned as the error.
+    fn call_at<R>(
+        &mut self,
+        index: usize,
+        hook: &'static str,
+        f: impl FnOnce(&mut dyn Plugin) -> R,
+    ) -> std::result::Result<R, String> {
+        let plugin = &mut self.plugins[index];
+        let record = &mut self.records[index];
+        let name = &record.name;
+
+        let start = Instant::now();
+        let outcome = if self.panic_policy == PanicPolicy::Propagate {
+            Ok(f(plugin.as_mut()))
+        } else {
+            catch_panic(name, &self.logger, || f(plugin.as_mut())).map_err(|cause| {
+                let message = panic_message(&cause).to_owned();
+                error!(
+                    self.logger,
+                    target: &log_target(name),
+                    "The plugin `{}` panicked in {}, and won't be called again: {}",
+                    name,
+                    hook,
+                    message
+                );
+                record.poisoned = true;
+                message
+            })
+        };
+        let over_budget = record_timing(
+            &mut self.timings,
+            self.limits,
+            &self.logger,
+            name,
+            hook,
+            start.elapsed(),
+        );
+        if over_budget && self.limits.poisons() && !record.poisoned {
+            error!(
+                self.logger,
+                target: &log_target(name),
+                "The plugin `{}` overran its time limit in {}, and won't be called again",
+                name,
+                hook
+            );
+            record.poisoned = true;
+        }
+        outcome
+    }
+
+    /// Every command the enabled plugins handle (see [`Plugin::commands`]), along with the name
+    /// of the plugin handling it, in the order the plugins were loaded. Poisoned plugins' commands
+    /// are left out, since they can't be run.
+    pub fn all_commands(&self) -> Vec<(String, CommandSpec)> {
+        self.records
+            .iter()
+            .filter(|record| record.enabled && !record.poisoned)
+            .flat_map(|record| {
+                record
+                    .commands
+                    .iter()
+                    .map(|command| (record.name.clone(), command.clone()))
+            })
+            .collect()
+    }
+
+    /// Run the command called `command` with `args`, through the `run_command()` of the plugin
+    /// handling it, and return its exit code. If several plugins handle a command by the same
+    /// name, the one loaded first runs it.
+    ///
+    /// Only the commands listed by [`PluginManager::all_commands`] can be run: anything else
+    /// fails with an `UnknownCommand` error. A plugin's error is returned as a `CommandFailed`
+    /// error. A panic is handled according to the manager's [`PanicPolicy`], the same as in
+    /// [`PluginManager::update_all`], and reported as a `PluginPanicked` error.
+    pub fn run_command(&mut self, command: &str, args: &[String]) -> Result<i32> {
+        let index = self
+            .records
+            .iter()
+            .position(|record| {
+                record.enabled
+                    && !record.poisoned
+                    && record.commands.iter().any(|c| c.name == command)
+            })
+            .ok_or_else(|| ErrorKind::UnknownCommand(command.to_owned()))?;
+
+        let name = self.records[index].name.clone();
+        match self.call_at(index, "run_command", |plugin| {
+            plugin.run_command(command, args)
+        }) {
+            Ok(Ok(code)) => Ok(code),
+            Ok(Err(e)) => bail!(ErrorKind::CommandFailed(
+                name,
+                command.to_owned(),
+                describe(&e)
+            )),
+            Err(message) => bail!(ErrorKind::PluginPanicked(name, message)),
+        }
+    }
+
+    /// Get how long the plugin called `name` has spent in each of its callbacks, i.e.
+    /// `on_plugin_load()`, `on_plugin_unload()`, and the ones fired by
+    /// [`update_all`](Self::update_all), [`dispatch_event`](Self::dispatch_event) and
+    /// [`send_message`](Self::send_message). Returns `None` if no plugin by that name has been
+    /// called yet.
+    ///
+    /// Timings are kept by name, so they add up across reloads and duplicates, and are still
+    /// around once the plugin has been unloaded.
+    pub fn timings(&self, name: &str) -> Option<PluginTimings> {
+        self.timings.get(name).cloned()
+    }
+
+    /// Ask every loaded plugin how it is doing, returning each plugin's name along with its
+    /// [`HealthStatus`], in the order they were loaded. Disabled plugins are asked too, since they
+    /// are still loaded.
+    ///
+    /// Poisoned plugins aren't called, and are reported as unhealthy. A plugin which panics in
+    /// `health_check()` is reported as unhealthy too, unless the panic is propagated; it isn't
+    /// poisoned, since this only borrows the manager immutably.
+    pub fn health(&self) -> Vec<(String, HealthStatus)> {
+        self.plugins
+            .iter()
+            .zip(&self.records)
+            .map(|(plugin, record)| {
+                let status = if record.poisoned {
+                    HealthStatus::Unhealthy("the plugin has panicked".to_owned())
+                } else if self.panic_policy == PanicPolicy::Propagate {
+                    plugin.health_check()
+                } else {
+                    catch_panic(&record.name, &self.logger, || plugin.health_check())
+                        .unwrap_or_else(|cause| {
+                            HealthStatus::Unhealthy(format!(
+                                "the health check panicked: {}",
+                                panic_message(&cause)
+                            ))
+                        })
+                };
+                (record.name.clone(), status)
+            })
+            .collect()
+    }
+
+    /// Take a snapshot of every loaded plugin, in the order they were loaded: its metadata, where
+    /// it was loaded from, whether it is enabled or poisoned, how it is doing (as reported by
+    /// [`PluginManager::health`]) and its [timings](PluginManager::timings). Unlike the plugins
+    /// themselves, the snapshots don't borrow the manager, so they can be held on to once it is
+    /// unlocked, e.g. across an `.await`.
+    pub fn snapshot(&self) -> Vec<PluginSnapshot> {
+        self.health()
+            .into_iter()
+            .zip(self.plugins.iter().zip(&self.records))
+            .map(|((_, health), (plugin, record))| PluginSnapshot {
+                id: record.id,
+                metadata: plugin.metadata(),
+                path: record.path.clone(),
+                enabled: record.enabled,
+                poisoned: record.poisoned,
+                health,
+                timings: self.timings.get(&record.name).cloned().unwrap_or_default(),
+            })
+            .collect()
+    }
+
+    /// Re-enable a plugin previously disabled with [`PluginManager::disable`].
+    pub fn enable(&mut self, name: &str) -> Result<()> {
+        let index = self.position(name)?;
+        self.records[index].enabled = true;
+        Ok(())
+    }
+
+    /// Stop a plugin from receiving [`update`](PluginManager::update_all) and
+    /// [`on_event`](PluginManager::dispatch_event) calls, while keeping it loaded. This is much
+    /// cheaper than unloading and later reloading it.
+    pub fn disable(&mut self, name: &str) -> Result<()> {
+        let index = self.position(name)?;
+        self.records[index].enabled = false;
+        Ok(())
+    }
+
+    /// Check whether a plugin is enabled. Plugins are enabled when they're loaded, and this returns
+    /// `false` for plugins which aren't loaded at all.
+    pub fn is_enabled(&self, name: &str) -> bool {
+        self.position(name)
+            .map(|index| self.records[index].enabled)
+            .unwrap_or(false)
+    }
+
+    /// The names of the plugins which have been poisoned by panicking (or by overrunning the
+    /// manager's [`ResourceLimits`]), and are no longer called by [`PluginManager::update_all`] or
+    /// [`PluginManager::dispatch_event`].
+    pub fn poisoned_plugins(&self) -> Vec<&str> {
+        self.records
+            .iter()
+            .filter(|record| record.poisoned)
+            .map(|record| record.name.as_str())
+            .collect()
+    }
+
+    /// Check whether a plugin has been poisoned (see
+    /// [`PluginManager::poisoned_plugins`]). This returns `false` for plugins which aren't loaded
+    /// at all.
+    pub fn is_poisoned(&self, name: &str) -> bool {
+        self.position(name)
+            .map(|index| self.records[index].poisoned)
+            .unwrap_or(false)
+    }
+
+    /// Give a poisoned plugin another chance, so it is called by
+    /// [`PluginManager::update_all`] and [`PluginManager::dispatch_event`] again, e.g. once
+    /// whatever made it panic has been dealt with. Nothing stops it from panicking again, at
+    /// which point it is poisoned again.
+    pub fn clear_poison(&mut self, name: &str) -> Result<()> {
+        let index = self.position(name)?;
+        self.records[index].poisoned = false;
+        Ok(())
+    }
+
+    /// Iterate over every loaded plugin, in the order they were loaded.
+    pub fn plugins(&self) -> impl Iterator<Item = &dyn Plugin> {
+        self.plugins.iter().map(|p| p.as_ref())
+    }
+
+    /// Iterate mutably over every loaded plugin, in the order they were loaded, e.g. to call a
+    /// host-specific method on each of them. Unlike [`PluginManager::update_all`], poisoned and
+    /// disabled plugins are included, and panics aren't caught.
+    pub fn plugins_mut(&mut self) -> impl Iterator<Item = &mut dyn Plugin> {
+        self.plugins.iter_mut().map(|p| p.as_mut())
+    }
+
+    /// Iterate over the names of every loaded plugin, in the order they were loaded.
+    pub fn plugin_names(&self) -> impl Iterator<Item = &str> {
+        self.records.iter().map(|r| r.name.as_str())
+    }
+
+    /// The names of every loaded plugin, sorted. Unlike [`PluginManager::plugin_names`], this
+    /// doesn't depend on the order plugins were loaded in (which, for folders, depends on the
+    /// order the filesystem lists them in), so it is suitable for output which has to be
+    /// reproducible.
+    ///
+    /// Names are compared byte by byte, so e.g. uppercase letters sort before lowercase ones.
+    pub fn sorted_plugin_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.plugin_names().collect();
+        names.sort();
+        names
+    }
+
+    /// Get the names of the plugins in a `group` (see [`PluginManager::load_plugin_in_group`]), in
+    /// the order they were loaded.
+    pub fn plugins_in_group(&self, group: &str) -> Vec<&str> {
+        self.records
+            .iter()
+            .filter(|record| record.group.as_deref() == Some(group))
+            .map(|record| record.name.as_str())
+            .collect()
+    }
+
+    /// Check whether a plugin with the given name is loaded.
+    pub fn is_loaded(&self, name: &str) -> bool {
+        self.records.iter().any(|r| r.name == name)
+    }
+
+    /// The number of loaded plugins.
+    pub fn len(&self) -> usize {
+        self.plugins.len()
+    }
+
+    /// Returns `true` if no plugins are loaded.
+    pub fn is_empty(&self) -> bool {
+        self.plugins.is_empty()
+    }
+
+    /// Collect the metadata of every loaded plugin, in the order they were loaded.
+    pub fn list_metadata(&self) -> Vec<PluginMetadata> {
+        self.plugins.iter().map(|p| p.metadata()).collect()
+    }
+
+    /// Find every loaded plugin whose metadata lists `capability` among its capabilities, in the
+    /// order they were loaded.
+    pub fn plugins_with_capability(&self, capability: &str) -> Vec<&dyn Plugin> {
+        self.plugins
+            .iter()
+            .filter(|p| p.metadata().capabilities.iter().any(|c| c == capability))
+            .map(|p| p.as_ref())
+            .collect()
+    }
+
+    /// Find every loaded plugin whose metadata matches `pred`, in the order they were loaded.
+    /// This covers any criteria the other lookups don't, e.g. every plugin by a given author with
+    /// `manager.find_plugins(|metadata| metadata.author == "Team A")`.
+    pub fn find_plugins<F: Fn(&PluginMetadata) -> bool>(&self, pred: F) -> Vec<&dyn Plugin> {
+        self.plugins
+            .iter()
+            .filter(|p| pred(&p.metadata()))
+            .map(|p| p.as_ref())
+            .collect()
+    }
+
+    /// Look up a loaded plugin by name.
+    pub fn get_plugin(&self, name: &str) -> Option<&dyn Plugin> {
+        let index = self.position(name).ok()?;
+        Some(self.plugins[index].as_ref())
+    }
+
+    /// Look up a loaded plugin by name, for calling methods which need mutable access.
+    pub fn get_plugin_mut(&mut self, name: &str) -> Option<&mut dyn Plugin> {
+        let index = self.position(name).ok()?;
+        Some(self.plugins[index].as_mut())
+    }
+
+    /// Look up a loaded plugin by name, and downcast it to its concrete type `T`. `None` is
+    /// returned if no such plugin is loaded, or if it isn't a `T` (see
+    /// [`as_any`](Plugin#method.as_any) for when that can happen).
+    pub fn get_plugin_as<T: Any>(&self, name: &str) -> Option<&T> {
+        self.get_plugin(name)?.as_any().downcast_ref()
+    }
+
+    /// Look up a loaded plugin by name, and downcast it to its concrete type `T` for calling
+    /// methods which need mutable access.
+    pub fn get_plugin_as_mut<T: Any>(&mut self, name: &str) -> Option<&mut T> {
+        self.get_plugin_mut(name)?.as_any_mut().downcast_mut()
+    }
+
+    /// Look up a loaded plugin by the id it was given when it was loaded.
+    pub fn get_plugin_by_id(&self, id: PluginId) -> Option<&dyn Plugin> {
+        self.records
+            .iter()
+            .position(|r| r.id == id)
+            .map(|index| self.plugins[index].as_ref())
+    }
+
+    /// Unload a single plugin by the id it was given when it was loaded, like
+    /// [`PluginManager::unload_plugin`].
+    pub fn unload_by_id(&mut self, id: PluginId) -> Result<()> {
+        match self.records.iter().position(|r| r.id == id) {
+            Some(index) => self.unload_at(index),
+            None => bail!(ErrorKind::PluginIdNotFound(id)),
+        }
+    }
+
+    /// Look up an extra symbol exported by the library a loaded plugin came from, such as a
+    /// function beyond the ones in the [`Plugin`] trait.
+    ///
+    /// An error is returned if no plugin with that name is loaded, or if its library doesn't
+    /// export `symbol`.
+    ///
+    /// # Safety
+    ///
+    /// The symbol is trusted to really have the type `T`, which must be pointer sized (usually a
+    /// function pointer), or this fails to compile. Calling into the symbol is subject to the same
+    /// care as calling the plugin itself.
+    pub unsafe fn get_symbol<T>(&self, plugin: &str, symbol: &[u8]) -> Result<Symbol<'_, T>> {
+        let library = match self.records[self.position(plugin)?].library {
+            Some(library) => &self.loaded_libraries[library],
+            None => bail!(ErrorKind::SymbolNotFound(
+                String::from_utf8_lossy(symbol).into_owned()
+            )),
+        };
+        Ok(Symbol::new(library.symbol(symbol)?))
+    }
+
+    /// The file a loaded plugin's library was loaded from, as it was passed to the manager.
+    /// Plugins [registered](PluginManager::register_plugin) directly have no file.
+    pub fn plugin_path(&self, name: &str) -> Option<&Path> {
+        self.position(name)
+            .ok()
+            .map(|index| &self.records[index])
+            .filter(|record| record.library.is_some())
+            .map(|record| record.path.as_path())
+    }
+
+    /// The number of plugin libraries currently loaded. A library stays loaded for as long as any
+    /// of its plugins is, so this can be fewer than the number of plugins, and plugins
+    /// [registered](PluginManager::register_plugin) directly don't have one at all.
+    pub fn library_count(&self) -> usize {
+        self.loaded_libraries.len()
+    }
+
+    /// The file each loaded library was loaded from, as it was passed to the manager, in the same
+    /// order they were loaded in. Libraries
+    /// [loaded from memory](PluginManager::load_plugin_from_bytes) are listed as `<memory>`.
+    pub fn loaded_library_paths(&self) -> Vec<&Path> {
+        self.library_paths.iter().map(PathBuf::as_path).collect()
+    }
+
+    /// Each loaded library, as listed by [`PluginManager::loaded_library_paths`], along with how
+    /// many of the loaded plugins were created from it. This is meant for diagnosing leaks: a
+    /// library is normally released as soon as its last plugin is unloaded, so a count of zero
+    /// flags an orphaned library. The one exception is a library whose plugin panicked while
+    /// loading under [`PanicPolicy::Propagate`], which is kept around on purpose (since the panic
+    /// may still refer to it) until the manager is unloaded.
+    pub fn library_plugin_counts(&self) -> Vec<(PathBuf, usize)> {
+        let mut counts = vec![0; self.loaded_libraries.len()];
+        for library in self.records.iter().filter_map(|record| record.library) {
+            counts[library] += 1;
+        }
+        self.library_paths.iter().cloned().zip(counts).collect()
+    }
+
+    /// The file each plugin was loaded from, in the same order as `plugins`.
+    #[cfg_attr(not(feature = "watch"), allow(dead_code))]
+    pub(crate) fn plugin_paths(&self) -> impl Iterator<Item = &Path> {
+        self.records.iter().map(|r| r.path.as_path())
+    }
+
+    /// The names of the plugins with the given ids, skipping any which aren't loaded.
+    pub(crate) fn names_of(&self, ids: &[PluginId]) -> Vec<String> {
+        ids.iter()
+            .filter_map(|&id| self.records.iter().find(|r| r.id == id))
+            .map(|record| record.name.clone())
+            .collect()
+    }
+
+    /// The ids of the plugins loaded from the file at `path`, however either was spelled, in the
+    /// order they were loaded.
+    fn loaded_from(&self, path: &Path) -> Vec<PluginId> {
+        let Ok(path) = fs::canonicalize(path) else {
+            return Vec::new();
+        };
+        self.records
+            .iter()
+            .filter(|r| {
+                r.source.as_ref() == Some(&path)
+                    || (r.has_file() && fs::canonicalize(&r.path).is_ok_and(|p| p == path))
+            })
+            .map(|r| r.id)
+            .collect()
+    }
+
+    /// Check nothing has been loaded from `filename` yet, before one of the loaders opens it. If
+    /// plugins have been and the manager [ignores](PluginManagerBuilder::ignore_already_loaded)
+    /// such loads, their ids are returned for the loader to hand back instead. Otherwise it is an
+    /// [`ErrorKind::AlreadyLoaded`] error.
+    pub(crate) fn check_not_loaded(&self, filename: &OsStr) -> Result<Option<Vec<PluginId>>> {
+        let path = Path::new(filename);
+        let ids = self.loaded_from(path);
+        if ids.is_empty() {
+            return Ok(None);
+        }
+        if self.ignore_already_loaded {
+            debug!(self.logger, "{:?} is already loaded, leaving it be", path);
+            return Ok(Some(ids));
+        }
+        bail!(ErrorKind::AlreadyLoaded(path.into()))
+    }
+
+    /// Find the index of the plugin with the given name.
+    fn position(&self, name: &str) -> Result<usize> {
+        match self.records.iter().position(|r| r.name == name) {
+            Some(index) => Ok(index),
+            None => bail!(ErrorKind::PluginNotFound(name.to_owned())),
+        }
+    }
+
+    /// Unload the plugin at `index`, firing its `on_plugin_unload()` and releasing its library if
+    /// nothing else needs it. The plugin is unloaded even if `on_plugin_unload()` fails.
+    fn unload_at(&mut self, index: usize) -> Result<()> {
+        let plugin = self.plugins.remove(index);
+        let record = self.records.remove(index);
+
+        debug!(self.logger, target: &log_target(&record.name), "Unloading plugin {:?}", record.name);
+        let (outcome, elapsed) = fire_unload(
+            plugin.as_ref(),
+            &record.name,
+            self.panic_policy,
+            self.on_unload.as_ref(),
+            self.capture_output,
+            &self.logger,
+        );
+        record_timing(
+            &mut self.timings,
+            self.limits,
+            &self.logger,
+            &record.name,
+            "on_plugin_unload",
+            elapsed,
+        );
+        // The plugin (and anything it registered) has to go before its library does, otherwise
+        // its vtable is left dangling.
+        drop(plugin);
+        self.context.remove_owned_by(record.id);
+        self.unsubscribe(record.id);
+
+        if let Some(library) = record.library {
+            self.release_library(library);
+        }
+
+        outcome
+    }
+
+    /// Drop every library once all the plugins are gone, in reverse order, except for the ones at
+    /// the indices in `leaked`, which are leaked instead.
+    fn release_all_libraries(&mut self, leaked: &HashSet<usize>) {
+        // Services registered by the plugins have their code in the plugins' libraries.
+        self.context.remove_plugin_services();
+        for (index, lib) in self.loaded_libraries.drain(..).enumerate().rev() {
+            if leaked.contains(&index) {
+                mem::forget(lib);
+            } else {
+                drop(lib);
+            }
+        }
+        self.library_paths.clear();
+        self.records.clear();
+        self.unsubscribe_all();
+    }
+
+    /// Drop the library at `index` if no remaining plugin was created from it, fixing up the
+    /// library indices of every plugin loaded after it.
+    fn release_library(&mut self, index: usize) {
+        if self.records.iter().any(|r| r.library == Some(index)) {
+            return;
+        }
+
+        drop(self.loaded_libraries.remove(index));
+        self.library_paths.remove(index);
+        for library in self.records.iter_mut().filter_map(|r| r.library.as_mut()) {
+            if *library > index {
+                *library -= 1;
+            }
+        }
+    }
+}
+
+/// The most messages sent by plugins [`PluginManager::deliver_messages`] delivers in one go.
+const MAX_DELIVERED_MESSAGES: usize = 1024;
+
+/// The longest [`PluginManager::load_plugin_with_retry`] waits in total between its attempts.
+const MAX_RETRY_WAIT: Duration = Duration::from_secs(60);
+
+/// The symbol plugin constructors are exported under by [`declare_plugin!`](crate::declare_plugin).
+const DEFAULT_CONSTRUCTOR: &[u8] = b"_plugin_create";
+
+/// The symbol libraries exporting several plugins export their constructor under, as done by
+/// [`declare_plugins!`](crate::declare_plugins).
+const BUNDLE_CONSTRUCTOR: &[u8] = b"_plugin_create_all";
+
+/// The symbol constructors which are told the host's version are exported under, as done by
+/// [`declare_versioned_plugin!`](crate::declare_versioned_plugin).
+const VERSIONED_CONSTRUCTOR: &[u8] = b"_plugin_create_v2";
+
+/// The symbol constructors taking a [`PluginConfig`] are exported under.
+const CONFIG_CONSTRUCTOR: &[u8] = b"_plugin_create_with_config";
+
+/// The plugins constructed from a library, which haven't had their `on_plugin_load()` fired or been
+/// registered with the manager yet. There is always at least one.
+pub(crate) struct Candidate {
+    // Declared before `library` so that the plugins are always dropped first.
+    pub(crate) plugins: Vec<Box<dyn Plugin>>,
+    /// `None` for a plugin [registered](PluginManager::register_plugin) from the host itself.
+    library: Option<Box<dyn LoadedLibrary>>,
+    path: PathBuf,
+}
+
+impl Candidate {
+    /// Copy the plugins' names, which the manager goes by from here on.
+    fn names(&self) -> Vec<String> {
+        self.plugins
+            .iter()
+            .map(|plugin| plugin.name().to_owned())
+            .collect()
+    }
+}
+
+/// The path recorded for plugins [registered](PluginManager::register_plugin) from the host
+/// itself, which weren't loaded from any file.
+const IN_PROCESS_PATH: &str = "<in-process>";
+
+/// Work out an order in which to load `candidates` so that every plugin comes after the plugins it
+/// depends on, returned as indices into `candidates`. Otherwise, plugins keep the order they were
+/// found in. Plugins from the same library are always loaded together, so dependencies between
+/// them are ignored.
+fn dependency_order(candidates: &[Candidate]) -> Result<Vec<usize>> {
+    let provides = |index: usize, dep: &str| {
+        candidates[index]
+            .plugins
+            .iter()
+            .any(|plugin| plugin.name() == dep)
+    };
+    let names: Vec<String> = candidates
+        .iter()
+        .map(|c| {
+            let names: Vec<&str> = c.plugins.iter().map(|p| p.name()).collect();
+            names.join("+")
+        })
+        .collect();
+    let dependencies: Vec<Vec<usize>> = (0..candidates.len())
+        .map(|index| {
+            candidates[index]
+                .plugins
+                .iter()
+                .flat_map(|plugin| plugin.dependencies())
+                .filter_map(|dep| (0..candidates.len()).find(|&i| provides(i, &dep)))
+                .filter(|&dep| dep != index)
+                .collect()
+        })
+        .collect();
+
+    let preferred: Vec<usize> = (0..names.len()).collect();
+    topological_order(&names, &dependencies, &preferred)
+}
+
+/// Order the indices into `names` so that each comes after its `dependencies`, and otherwise in
+/// the `preferred` order. Fails with a `DependencyCycle` error, naming the entries involved, if
+/// there is no such order.
+fn topological_order(
+    names: &[String],
+    dependencies: &[Vec<usize>],
+    preferred: &[usize],
+) -> Result<Vec<usize>> {
+    let mut visited = vec![false; names.len()];
+    let mut stack = Vec::new();
+    let mut order = Vec::with_capacity(names.len());
+
+    fn visit(
+        index: usize,
+        names: &[String],
+        dependencies: &[Vec<usize>],
+        visited: &mut [bool],
+        stack: &mut Vec<usize>,
+        order: &mut Vec<usize>,
+    ) -> Result<()> {
+        if let Some(start) = stack.iter().position(|&i| i == index) {
+            let cycle = stack[start..]
+                .iter()
+                .chain(Some(&index))
+                .map(|&i| names[i].clone())
+                .collect();
+            bail!(ErrorKind::DependencyCycle(cycle));
+        }
+        if visited[index] {
+            return Ok(());
+        }
+
+        stack.push(index);
+        for &dep in &dependencies[index] {
+            visit(dep, names, dependencies, visited, stack, order)?;
+        }
+        stack.pop();
+
+        visited[index] = true;
+        order.push(index);
+        Ok(())
+    }
+
+    for &index in preferred {
+        visit(
+            index,
+            names,
+            dependencies,
+            &mut visited,
+            &mut stack,
+            &mut order,
+        )?;
+    }
+
+    Ok(order)
+}
+
+/// A handle to a loaded plugin, returned when it is loaded.
+///
+/// Ids are never reused by a manager, so an id stays valid (and keeps referring to the same plugin)
+/// no matter which other plugins are loaded or unloaded, until its own plugin is unloaded.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PluginId(pub(crate) u64);
+
+impl fmt::Display for PluginId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+/// The outcome of loading a folder of plugins, listing every candidate file that was attempted.
+#[derive(Debug, Default)]
+pub struct LoadReport {
+    /// Files which were loaded successfully.
+    pub loaded: Vec<PathBuf>,
+    /// The names of the plugins loaded from those files, in the order they were loaded. A file
+    /// can hold several plugins, so this may be longer than `loaded`.
+    pub plugins: Vec<String>,
+    /// Files which failed to load, along with the reason why.
+    pub failed: Vec<(PathBuf, Error)>,
+}
+
+impl LoadReport {
+    /// Returns `true` if no candidate failed to load.
+    pub fn is_success(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+/// The plugins taken out of a manager by [`PluginManager::drain`], along with the libraries their
+/// code lives in.
+///
+/// A plugin's vtable and methods live in its library, so the two have to go together: the plugins
+/// can be looked at and called for as long as this is alive, but can't be moved out of it. When
+/// it is dropped, the plugins are dropped first, in the reverse of the order they were loaded in,
+/// and only then their libraries.
+pub struct DrainedPlugins {
+    plugins: Vec<Box<dyn Plugin>>,
+    libraries: Vec<Box<dyn LoadedLibrary>>,
+}
+
+impl DrainedPlugins {
+    /// The plugins, in the order they were loaded in.
+    pub fn iter(&self) -> impl Iterator<Item = &dyn Plugin> {
+        self.plugins.iter().map(|p| p.as_ref())
+    }
+
+    /// The plugins, in the order they were loaded in, mutably.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut dyn Plugin> {
+        self.plugins.iter_mut().map(|p| p.as_mut())
+    }
+
+    /// Look up a plugin by name, returning the first one loaded if there are several.
+    pub fn get(&self, name: &str) -> Option<&dyn Plugin> {
+        self.iter().find(|p| p.name() == name)
+    }
+
+    /// Look up a plugin by name, mutably.
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut dyn Plugin> {
+        self.iter_mut().find(|p| p.name() == name)
+    }
+
+    /// How many plugins there are.
+    pub fn len(&self) -> usize {
+        self.plugins.len()
+    }
+
+    /// Check whether there are no plugins.
+    pub fn is_empty(&self) -> bool {
+        self.plugins.is_empty()
+    }
+}
+
+impl Drop for DrainedPlugins {
+    fn drop(&mut self) {
+        while let Some(plugin) = self.plugins.pop() {
+            drop(plugin);
+        }
+        while let Some(library) = self.libraries.pop() {
+            drop(library);
+        }
+    }
+}
+
+impl fmt::Debug for DrainedPlugins {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.iter().map(|p| p.name()))
+            .finish()
+    }
+}
+
+/// How a plugin's `on_plugin_load()` went (including whether it panicked), and how long it took.
+type LoadOutcome = (thread::Result<Result<()>>, Duration);
+
+/// Fire the `on_plugin_load()` of each freshly constructed plugin in turn, catching any panic so
+/// that the caller can decide what to do with it. This stops at the first plugin which fails, so
+/// there may be fewer outcomes than plugins.
+fn fire_load_all(
+    plugins: &[Box<dyn Plugin>],
+    names: &[String],
+    context: &mut PluginContext,
+    ids: &[PluginId],
+    capture: bool,
+    logger: &Logger,
+) -> Vec<LoadOutcome> {
+    let mut outcomes = Vec::with_capacity(plugins.len());
+    for ((plugin, name), &id) in plugins.iter().zip(names).zip(ids) {
+        trace!(
+            logger,
+            target: &log_target(name),
+            "Firing on_plugin_load for {:?}",
+            name
+        );
+        // The plugin is thrown away if it panics, so nobody gets to observe it in a broken state.
+        context.set_current(Some(id));
+        let start = Instant::now();
+        let outcome = with_output(capture, logger, name, "on_plugin_load", || {
+            catch_panic(name, logger, || plugin.on_plugin_load(context))
+        });
+        let elapsed = start.elapsed();
+        context.set_current(None);
+
+        let failed = !matches!(outcome, Ok(Ok(())));
+        outcomes.push((outcome, elapsed));
+        if failed {
+            break;
+        }
+    }
+    outcomes
+}
+
+/// Fire a plugin's `on_plugin_unload()`, turning an error or a caught panic into an error we own, so
+/// the caller can carry on tearing down the remaining plugins (and their libraries). The `hook` is
+/// notified of how it went, unless the panic is propagated. Returns how long it took, too.
+fn fire_unload(
+    plugin: &dyn Plugin,
+    name: &str,
+    policy: PanicPolicy,
+    hook: Option<&LifecycleHook>,
+    capture: bool,
+    logger: &Logger,
+) -> (Result<()>, Duration) {
+    let start = Instant::now();
+    let outcome = with_output(capture, logger, name, "on_plugin_unload", || {
+        call_unload(plugin, name, policy, logger)
+    });
+    let elapsed = start.elapsed();
+    if let Some(hook) = hook {
+        hook(&plugin.metadata(), elapsed, &outcome);
+    }
+    (outcome, elapsed)
+}
+
+/// Note down that the plugin called `name` spent `elapsed` in its `hook` callback, warning about
+/// it if that is longer than the `limits` allow. Returns whether it was.
+fn record_timing(
+    timings: &mut HashMap<String, PluginTimings>,
+    limits: ResourceLimits,
+    logger: &Logger,
+    name: &str,
+    hook: &'static str,
+    elapsed: Duration,
+) -> bool {
+    let over_budget = limits.exceeded_by(elapsed);
+    if over_budget {
+        warn!(
+            logger,
+            target: &log_target(name),
+            "The plugin `{}` took {:?} in {}, which is over its time limit",
+            name,
+            elapsed,
+            hook
+        );
+    }
+
+    match timings.get_mut(name) {
+        Some(timings) => timings.record(hook, elapsed, over_budget),
+        None => timings
+            .entry(name.to_owned())
+            .or_default()
+            .record(hook, elapsed, over_budget),
+    }
+    over_budget
+}
+
+/// Fire a plugin's `on_plugin_unload()`, as described by [`fire_unload`].
+fn call_unload(
+    plugin: &dyn Plugin,
+    name: &str,
+    policy: PanicPolicy,
+    logger: &Logger,
+) -> Result<()> {
+    if policy == PanicPolicy::Propagate {
+        return plugin
+            .on_plugin_unload()
+            .map_err(|e| ErrorKind::PluginUnloadFailed(name.to_owned(), describe(&e)).into());
+    }
+
+    match catch_panic(name, logger, || plugin.on_plugin_unload()) {
+        Ok(Ok(())) => Ok(()),
+        Ok(Err(e)) => bail!(ErrorKind::PluginUnloadFailed(name.to_owned(), describe(&e))),
+        Err(cause) => bail!(ErrorKind::PluginPanicked(
+            name.to_owned(),
+            panic_message(&cause).to_owned()
+        )),
+    }
+}
+
+/// Run `f`, one of `plugin`'s `hook` callbacks, capturing what it prints if `capture` is set (see
+/// [`PluginManagerBuilder::capture_output`]).
+fn with_output<R>(
+    capture: bool,
+    logger: &Logger,
+    plugin: &str,
+    hook: &str,
+    f: impl FnOnce() -> R,
+) -> R {
+    #[cfg(feature = "capture")]
+    if capture {
+        return crate::capture::capture(plugin, hook, logger, f);
+    }
+
+    #[cfg(not(feature = "capture"))]
+    let _ = (capture, logger, plugin, hook);
+    f()
+}
+
+/// Ask a plugin for its state ahead of a reload, catching a panic unless `policy` says otherwise.
+fn save_state(
+    plugin: &dyn Plugin,
+    name: &str,
+    policy: PanicPolicy,
+    logger: &Logger,
+) -> Option<Vec<u8>> {
+    if policy == PanicPolicy::Propagate {
+        return plugin.save_state();
+    }
+
+    match catch_panic(name, logger, || plugin.save_state()) {
+        Ok(state) => state,
+        Err(cause) => {
+            error!(
+                logger,
+                target: &log_target(name),
+                "The plugin `{}` panicked in save_state, so its state is lost: {}",
+                name,
+                panic_message(&cause)
+            );
+            None
+        }
+    }
+}
+
+/// Hand a freshly constructed plugin the state saved from its predecessor, turning a caught
+/// panic into an error.
+fn restore_state(
+    plugin: &mut dyn Plugin,
+    state: &[u8],
+    policy: PanicPolicy,
+    logger: &Logger,
+) -> Result<()> {
+    if policy == PanicPolicy::Propagate {
+        plugin.restore_state(state);
+        return Ok(());
+    }
+
+    match catch_panic(plugin.name(), logger, || plugin.restore_state(state)) {
+        Ok(()) => Ok(()),
+        Err(cause) => bail!(ErrorKind::PluginPanicked(
+            plugin.name().to_owned(),
+            panic_message(&cause).to_owned()
+        )),
+    }
+}
+
+/// Flatten an error and its chain of causes into a single message.
+///
+/// Errors handed to us by a plugin may contain trait objects whose vtables live inside the plugin's
+/// library, so they have to be turned into something we own before that library is dropped.
+fn describe(e: &Error) -> String {
+    e.iter()
+        .map(|cause| cause.to_string())
+        .collect::<Vec<_>>()
+        .join(": ")
+}
+
+/// The `log` target the manager's messages about a particular plugin are sent to, so they can be
+/// filtered per plugin (e.g. with `RUST_LOG=plugin::greeter=trace`).
+pub(crate) fn log_target(name: &str) -> String {
+    format!("plugin::{}", name)
+}
+
+/// The [`PluginRegistrar`] handed to `_plugin_create_all`, collecting each plugin into the
+/// `Vec<*mut dyn Plugin>` behind `registry`.
+#[allow(improper_ctypes_definitions)]
+unsafe extern "C-unwind" fn collect_plugin(registry: *mut c_void, plugin: *mut dyn Plugin) {
+    (*registry.cast::<Vec<*mut dyn Plugin>>()).push(plugin);
+}
+
+/// Pull a human readable message out of a caught panic's payload.
+fn panic_message(cause: &Box<dyn Any + Send>) -> &str {
+    if let Some(message) = cause.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = cause.downcast_ref::<String>() {
+        message
+    } else {
+        "Box<dyn Any>"
+    }
+}
+
+/// Remove the files which plugins were already `loaded` from from `paths`, comparing canonical
+/// paths.
+pub(crate) fn skip_loaded<'a>(
+    loaded: impl Iterator<Item = &'a Path>,
+    paths: &mut Vec<PathBuf>,
+    logger: &Logger,
+) {
+    let loaded: HashSet<PathBuf> = loaded
+        .filter_map(|path| fs::canonicalize(path).ok())
+        .collect();
+    if loaded.is_empty() {
+        return;
+    }
+
+    paths.retain(|path| match fs::canonicalize(path) {
+        Ok(canonical) if loaded.contains(&canonical) => {
+            trace!(logger, "Skipping {:?}, since it is already loaded", path);
+            false
+        }
+        _ => true,
+    });
+}
+
+/// The extension shared libraries carry on the platform we were compiled for.
+pub(crate) const PLUGIN_EXTENSION: &str = if cfg!(target_os = "windows") {
+    "dll"
+} else if cfg!(target_os = "macos") {
+    "dylib"
+} else {
+    "so"
+};
+
+/// Check whether a path names a shared library for the current platform, based purely on its
+/// extension. This doesn't touch the filesystem.
+pub(crate) fn is_plugin_library(path: &Path) -> bool {
+    #[cfg(feature = "wasm")]
+    if crate::wasm::is_wasm(path) {
+        return true;
+    }
+
+    path.extension() == Some(OsStr::new(PLUGIN_EXTENSION))
+}
+
+/// Check whether a file name matches a glob `pattern`, where `*` stands for any run of characters
+/// (including none) and `?` for exactly one. Everything else has to match literally.
+fn matches_glob(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Where to pick up again if what follows the last `*` stops matching: just after the `*`,
+    // with it having swallowed one more character of the name.
+    let mut backtrack = None;
+
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p + 1, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((after_star, swallowed)) => {
+                    p = after_star;
+                    n = swallowed + 1;
+                    backtrack = Some((after_star, swallowed + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+impl Default for PluginManager {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Build a manager with the default configuration out of plugins which are already part of the
+/// host, registering each of them in turn with [`PluginManager::register_plugin`].
+///
+/// Since collecting can't fail, a plugin which is rejected (e.g. because its name is already
+/// taken) or fails in `on_plugin_load()` is logged and dropped, and the rest are still
+/// registered. Use `register_plugin` directly to handle the errors.
+impl FromIterator<Box<dyn Plugin>> for PluginManager {
+    fn from_iter<I: IntoIterator<Item = Box<dyn Plugin>>>(plugins: I) -> Self {
+        let mut manager = PluginManager::new();
+        for plugin in plugins {
+            if let Err(e) = manager.register_plugin(plugin) {
+                warn!(manager.logger, "{}", e);
+            }
+        }
+        manager
+    }
+}
+
+/// We implement `Drop` for PluginManager, so that plugins are always unloaded when the `PluginManager`
+/// gets dropped. This gives them a chance to do any necessary cleanup.
+impl Drop for PluginManager {
+    fn drop(&mut self) {
+        if self.plugins.is_empty() && self.loaded_libraries.is_empty() {
+            return;
+        }
+
+        // A panic escaping from here while the thread is already unwinding would abort the
+        // process, so it is caught even under `PanicPolicy::Propagate`.
+        match panic::catch_unwind(AssertUnwindSafe(|| self.unload())) {
+            Ok(Ok(())) => {}
+            Ok(Err(failures)) => {
+                for (name, e) in failures {
+                    error!(
+                        self.logger,
+                        target: &log_target(&name),
+                        "Failed to unload the plugin `{}`: {}",
+                        name,
+                        e
+                    );
+                }
+            }
+            Err(cause) => error!(
+                self.logger,
+                "A plugin panicked while the plugin manager was being dropped: {}",
+                panic_message(&cause)
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loader::mock::MockLoader;
+    use crate::message::MessageSender;
+    use std::ptr;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn only_the_platform_library_extension_counts() {
+        let native = if cfg!(target_os = "windows") {
+            "dll"
+        } else if cfg!(target_os = "macos") {
+            "dylib"
+        } else {
+            "so"
+        };
+        assert!(is_plugin_library(Path::new(&format!(
+            "target/debug/libgreeter.{}",
+            native
+        ))));
+
+        for other in ["so", "dll", "dylib"]
+            .into_iter()
+            .filter(|&ext| ext != native)
+        {
+            assert!(!is_plugin_library(Path::new(&format!(
+                "libgreeter.{}",
+                other
+            ))));
+        }
+        for name in [
+            "libgreeter.d",
+            "greeter.pdb",
+            "notes.txt",
+            "libgreeter",
+            native,
+        ] {
+            assert!(!is_plugin_library(Path::new(name)), "{:?}", name);
+        }
+    }
+
+    #[test]
+    fn a_panic_message_is_read_from_either_payload_type() {
+        let literal = panic::catch_unwind(|| panic!("boom")).unwrap_err();
+        assert_eq!(panic_message(&literal), "boom");
+
+        let formatted = panic::catch_unwind(|| panic!("boom {}", 2)).unwrap_err();
+        assert_eq!(panic_message(&formatted), "boom 2");
+
+        let other = panic::catch_unwind(|| panic::panic_any(7)).unwrap_err();
+        assert_eq!(panic_message(&other), "Box<dyn Any>");
+    }
+
+    /// A plugin which notes down every callback made to it in a log shared with the test.
+    #[derive(Default)]
+    struct TestPlugin {
+        name: &'static str,
+        calls: Arc<Mutex<Vec<String>>>,
+        dependencies: Vec<String>,
+        /// Panic in every callback after loading, once it has been noted down.
+        panicky: bool,
+        subscriptions: Vec<String>,
+        /// Set when a service this plugin registers while loading is dropped.
+        service_dropped: Option<Arc<AtomicBool>>,
+        shutdown_priority: i32,
+    }
+
+    impl TestPlugin {
+        fn new(name: &'static str, calls: &Arc<Mutex<Vec<String>>>) -> Self {
+            Self {
+                name,
+                calls: Arc::clone(calls),
+                ..Self::default()
+            }
+        }
+
+        fn note(&self, call: &str) {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("{} {}", call, self.name));
+            if self.panicky {
+                panic!("{} panicked in {}", self.name, call);
+            }
+        }
+    }
+
+    impl Plugin for TestPlugin {
+        fn name(&self) -> &'static str {
+            self.name
+        }
+
+        fn dependencies(&self) -> Vec<String> {
+            self.dependencies.clone()
+        }
+
+        fn subscriptions(&self) -> Vec<String> {
+            self.subscriptions.clone()
+        }
+
+        fn shutdown_priority(&self) -> i32 {
+            self.shutdown_priority
+        }
+
+        fn update(&mut self, _delta: Duration) {
+            self.note("update");
+        }
+
+        fn on_event(&mut self, event: &PluginEvent) {
+            self.note(&format!("event {}", event.topic));
+        }
+
+        fn on_plugin_load(&self, ctx: &mut PluginContext) -> Result<()> {
+            if let Some(dropped) = &self.service_dropped {
+                ctx.register(self.name, DropFlag(Arc::clone(dropped)));
+            }
+            Ok(())
+        }
+
+        fn on_plugin_unload(&self) -> Result<()> {
+            self.note("unload");
+            Ok(())
+        }
+
+        fn health_check(&self) -> HealthStatus {
+            self.note("health");
+            HealthStatus::Ok
+        }
+    }
+
+    /// Sets its flag when dropped.
+    struct DropFlag(Arc<AtomicBool>);
+
+    impl Drop for DropFlag {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    fn calls() -> Arc<Mutex<Vec<String>>> {
+        Arc::new(Mutex::new(Vec::new()))
+    }
+
+    /// A manager with a [`TestPlugin`] loaded under each of `names`, noting down into `calls`.
+    fn manager(names: &[&'static str], calls: &Arc<Mutex<Vec<String>>>) -> PluginManager {
+        let mut manager = PluginManager::new();
+        for &name in names {
+            let plugin = TestPlugin {
+                panicky: name == "panicky",
+                ..TestPlugin::new(name, calls)
+            };
+            manager.register_plugin(Box::new(plugin)).unwrap();
+        }
+        manager
+    }
+
+    #[test]
+    fn an_unload_panic_is_caught() {
+        let calls = calls();
+        let plugin = TestPlugin {
+            panicky: true,
+            ..TestPlugin::new("panicky", &calls)
+        };
+        let e = fire_unload(
+            &plugin,
+            "panicky",
+            PanicPolicy::Catch,
+            None,
+            false,
+            &Logger::default(),
+        )
+        .0
+        .unwrap_err();
+        assert!(matches!(e.kind(), ErrorKind::PluginPanicked(..)), "{}", e);
+        assert_eq!(*calls.lock().unwrap(), ["unload panicky"]);
+    }
+
+    fn needs(name: &'static str, dependencies: &[&str]) -> Candidate {
+        let plugin = TestPlugin {
+            dependencies: dependencies.iter().map(|&dep| dep.to_owned()).collect(),
+            ..TestPlugin::new(name, &calls())
+        };
+        Candidate {
+            plugins: vec![Box::new(plugin)],
+            library: None,
+            path: PathBuf::from(name),
+        }
+    }
+
+    #[test]
+    fn a_dependency_chain_is_ordered_dependencies_first() {
+        let candidates = [
+            needs("c", &["b"]),
+            needs("b", &["a", "elsewhere"]),
+            needs("a", &[]),
+        ];
+        assert_eq!(dependency_order(&candidates).unwrap(), [2, 1, 0]);
+    }
+
+    #[test]
+    fn a_dependency_cycle_names_the_plugins_in_it() {
+        let candidates = [needs("a", &["b"]), needs("b", &["a"]), needs("c", &[])];
+        let e = dependency_order(&candidates).unwrap_err();
+        match e.kind() {
+            ErrorKind::DependencyCycle(cycle) => assert_eq!(*cycle, ["a", "b", "a"]),
+            kind => panic!("expected a dependency cycle, got {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn a_missing_dependency_is_rejected_before_loading() {
+        let calls = calls();
+        let mut manager = PluginManager::new();
+        let e = manager
+            .register_plugin(Box::new(TestPlugin {
+                dependencies: vec!["absent".to_owned()],
+                ..TestPlugin::new("needy", &calls)
+            }))
+            .unwrap_err();
+        match e.kind() {
+            ErrorKind::MissingDependency(plugin, needs) => {
+                assert_eq!((plugin.as_str(), needs.as_str()), ("needy", "absent"))
+            }
+            kind => panic!("expected a missing dependency, got {:?}", kind),
+        }
+        assert!(calls.lock().unwrap().is_empty());
+        assert!(manager.is_empty());
+    }
+
+    #[test]
+    fn every_plugin_is_updated_even_if_one_panics() {
+        let calls = calls();
+        let mut manager = manager(&["first", "panicky", "last"], &calls);
+
+        manager.update_all(Duration::from_millis(16));
+        assert_eq!(
+            *calls.lock().unwrap(),
+            ["update first", "update panicky", "update last"]
+        );
+        assert_eq!(manager.poisoned_plugins(), ["panicky"]);
+
+        // Once poisoned, the plugin isn't called again.
+        calls.lock().unwrap().clear();
+        manager.update_all(Duration::from_millis(16));
+        assert_eq!(*calls.lock().unwrap(), ["update first", "update last"]);
+    }
+
+    #[test]
+    fn a_propagated_panic_reaches_the_host() {
+        let calls = calls();
+        let mut manager = manager(&["first", "panicky", "last"], &calls);
+        manager.panic_policy = PanicPolicy::Propagate;
+
+        let updated = panic::catch_unwind(AssertUnwindSafe(|| manager.update_all(Duration::ZERO)));
+        assert!(updated.is_err());
+        assert_eq!(*calls.lock().unwrap(), ["update first", "update panicky"]);
+        assert!(manager.poisoned_plugins().is_empty());
+
+        // Otherwise `panicky` would panic again on its way out.
+        manager.panic_policy = PanicPolicy::Catch;
+    }
+
+    #[test]
+    fn an_event_reaches_every_plugin() {
+        let calls = calls();
+        let mut manager = manager(&["first", "panicky", "last"], &calls);
+
+        manager.dispatch_event(&PluginEvent::with_payload("user_logged_in", 42u32));
+        assert_eq!(
+            *calls.lock().unwrap(),
+            [
+                "event user_logged_in first",
+                "event user_logged_in panicky",
+                "event user_logged_in last",
+            ]
+        );
+    }
+
+    #[test]
+    fn unload_reports_failures_and_still_unloads_everything() {
+        let calls = calls();
+        let mut manager = manager(&["first", "panicky", "last"], &calls);
+
+        let failures = manager.unload().unwrap_err();
+        let names: Vec<&str> = failures.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["panicky"]);
+        assert!(manager.plugins.is_empty());
+        assert!(manager.loaded_libraries.is_empty());
+    }
+
+    #[test]
+    fn a_disabled_plugin_stays_loaded_but_isnt_called() {
+        let calls = calls();
+        let mut manager = manager(&["first", "last"], &calls);
+
+        manager.disable("first").unwrap();
+        assert!(!manager.is_enabled("first"));
+        manager.dispatch_event(&PluginEvent::new("tick"));
+        assert_eq!(*calls.lock().unwrap(), ["event tick last"]);
+        assert!(manager.get_plugin("first").is_some());
+
+        manager.enable("first").unwrap();
+        assert!(manager.is_enabled("first"));
+        assert!(!manager.is_enabled("absent"));
+        assert!(manager.disable("absent").is_err());
+    }
+
+    /// A plugin with nothing but a name, for the constructors handed out by a [`MockLoader`].
+    struct Named(&'static str);
+
+    impl Plugin for Named {
+        fn name(&self) -> &'static str {
+            self.0
+        }
+    }
+
+    #[allow(improper_ctypes_definitions)]
+    extern "C-unwind" fn create_alpha() -> *mut dyn Plugin {
+        Box::into_raw(Box::new(Named("alpha")))
+    }
+
+    #[test]
+    fn libraries_load_and_unload_through_the_loader() {
+        let loader = MockLoader::default().library(
+            "alpha.so",
+            &[(DEFAULT_CONSTRUCTOR, create_alpha as *const c_void)],
+        );
+        let open = loader.open_count();
+        let mut manager = PluginManager::with_loader(loader);
+
+        let id = unsafe { manager.load_plugin("alpha.so") }.unwrap();
+        assert_eq!(manager.get_plugin_by_id(id).unwrap().name(), "alpha");
+        assert_eq!(manager.loaded_libraries.len(), 1);
+        assert_eq!(open.load(Ordering::SeqCst), 1);
+
+        manager.unload_plugin("alpha").unwrap();
+        assert!(manager.plugins.is_empty());
+        assert!(manager.loaded_libraries.is_empty());
+        assert_eq!(open.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn a_library_the_loader_cant_open_is_an_error() {
+        let mut manager = PluginManager::with_loader(MockLoader::default());
+        let e = unsafe { manager.load_plugin("missing.so") }.unwrap_err();
+        assert!(matches!(e.kind(), ErrorKind::LibraryLoadFailed(_)));
+        assert!(manager.plugins.is_empty());
+    }
+
+    #[allow(improper_ctypes_definitions)]
+    extern "C-unwind" fn create_panicking() -> *mut dyn Plugin {
+        panic!("this plugin can't be constructed")
+    }
+
+    // This can't be a fixture library: a cdylib has its own copy of the standard library, and a
+    // panic from one copy can't be caught by another.
+    #[test]
+    fn a_panicking_constructor_changes_nothing() {
+        let loader = MockLoader::default()
+            .library(
+                "alpha.so",
+                &[(DEFAULT_CONSTRUCTOR, create_alpha as *const c_void)],
+            )
+            .library(
+                "panicking.so",
+                &[(DEFAULT_CONSTRUCTOR, create_panicking as *const c_void)],
+            );
+        let open = loader.open_count();
+        let mut manager = PluginManager::with_loader(loader);
+        unsafe { manager.load_plugin("alpha.so") }.unwrap();
+
+        let e = unsafe { manager.load_plugin("panicking.so") }.unwrap_err();
+        assert!(matches!(e.kind(), ErrorKind::ConstructorPanicked(_)));
+        assert!(manager.plugin_names().eq(["alpha"]));
+        assert_eq!(manager.loaded_libraries.len(), 1);
+        assert_eq!(open.load(Ordering::SeqCst), 1);
+    }
+
+    fn answer() -> u32 {
+        42
+    }
+
+    #[test]
+    fn extra_symbols_are_looked_up_in_the_plugins_library() {
+        let loader = MockLoader::default().library(
+            "alpha.so",
+            &[
+                (DEFAULT_CONSTRUCTOR, create_alpha as *const c_void),
+                (b"answer", answer as *const c_void),
+            ],
+        );
+        let mut manager = PluginManager::with_loader(loader);
+        unsafe { manager.load_plugin("alpha.so") }.unwrap();
+
+        let found = unsafe { manager.get_symbol::<fn() -> u32>("alpha", b"answer") }.unwrap();
+        assert_eq!((*found)(), 42);
+
+        let e = unsafe { manager.get_symbol::<fn() -> u32>("alpha", b"question") }.unwrap_err();
+        assert!(matches!(e.kind(), ErrorKind::SymbolNotFound(_)), "{}", e);
+        let e = unsafe { manager.get_symbol::<fn() -> u32>("beta", b"answer") }.unwrap_err();
+        assert!(matches!(e.kind(), ErrorKind::PluginNotFound(_)), "{}", e);
+    }
+
+    #[test]
+    fn a_library_missing_a_required_symbol_isnt_constructed() {
+        let loader = MockLoader::default()
+            .library(
+                "alpha.so",
+                &[(DEFAULT_CONSTRUCTOR, create_alpha as *const c_void)],
+            )
+            .library(
+                "panicking.so",
+                &[(DEFAULT_CONSTRUCTOR, create_panicking as *const c_void)],
+            );
+        let open = loader.open_count();
+        let mut manager = PluginManager::with_loader(loader);
+
+        // The constructor would panic if it was called.
+        let e = unsafe { manager.load_plugin_requiring("panicking.so", &[b"answer"]) }.unwrap_err();
+        assert!(matches!(e.kind(), ErrorKind::SymbolNotFound(symbol) if symbol == "answer"));
+        assert_eq!(open.load(Ordering::SeqCst), 0);
+
+        unsafe { manager.load_plugin_requiring("alpha.so", &[DEFAULT_CONSTRUCTOR]) }.unwrap();
+        assert!(manager.plugin_names().eq(["alpha"]));
+    }
+
+    #[test]
+    fn plugins_unload_in_reverse_load_order() {
+        let calls = calls();
+        let mut manager = manager(&["a", "b", "c"], &calls);
+
+        manager.unload().unwrap();
+        assert_eq!(*calls.lock().unwrap(), ["unload c", "unload b", "unload a"]);
+    }
+
+    #[test]
+    fn a_built_manager_uses_its_constructor_symbol() {
+        let loader = MockLoader::default().library(
+            "alpha.so",
+            &[(b"_alpha_create", create_alpha as *const c_void)],
+        );
+        let mut manager = PluginManager::builder()
+            .constructor_symbol(b"_alpha_create")
+            .loader(loader)
+            .build();
+
+        unsafe { manager.load_plugin("alpha.so") }.unwrap();
+        assert!(manager.plugin_names().eq(["alpha"]));
+    }
+
+    /// Registers a service while loading, which takes a while.
+    struct Slow;
+
+    impl Plugin for Slow {
+        fn name(&self) -> &'static str {
+            "slow"
+        }
+
+        fn on_plugin_load(&self, ctx: &mut PluginContext) -> Result<()> {
+            thread::sleep(Duration::from_millis(200));
+            ctx.register("slow", 1u32);
+            Ok(())
+        }
+    }
+
+    #[allow(improper_ctypes_definitions)]
+    extern "C-unwind" fn create_slow() -> *mut dyn Plugin {
+        Box::into_raw(Box::new(Slow))
+    }
+
+    fn slow_manager() -> PluginManager {
+        let loader = MockLoader::default()
+            .library(
+                "alpha.so",
+                &[(DEFAULT_CONSTRUCTOR, create_alpha as *const c_void)],
+            )
+            .library(
+                "slow.so",
+                &[(DEFAULT_CONSTRUCTOR, create_slow as *const c_void)],
+            );
+        let mut manager = PluginManager::with_loader(loader);
+        manager.context_mut().register("host", 0u32);
+        manager
+    }
+
+    #[test]
+    fn a_timed_load_merges_what_the_plugin_registered() {
+        let mut manager = slow_manager();
+        let id = unsafe { manager.load_plugin_with_timeout("slow.so", Duration::from_secs(10)) }
+            .unwrap();
+
+        assert_eq!(manager.get_plugin_by_id(id).unwrap().name(), "slow");
+        assert_eq!(manager.context().get::<u32>("host"), Some(&0));
+        assert_eq!(manager.context().get::<u32>("slow"), Some(&1));
+    }
+
+    #[test]
+    fn a_timed_out_load_keeps_the_host_services_and_ids() {
+        let mut manager = slow_manager();
+        let e = unsafe { manager.load_plugin_with_timeout("slow.so", Duration::from_millis(50)) }
+            .unwrap_err();
+        assert!(matches!(e.kind(), ErrorKind::LoadTimeout(..)));
+        assert_eq!(manager.context().get::<u32>("host"), Some(&0));
+
+        let id = unsafe { manager.load_plugin("alpha.so") }.unwrap();
+        assert_eq!(id, PluginId(0));
+    }
+
+    #[allow(improper_ctypes_definitions)]
+    unsafe extern "C-unwind" fn create_pair(register: PluginRegistrar, registry: *mut c_void) {
+        for name in ["first", "second"] {
+            register(registry, Box::into_raw(Box::new(Named(name))));
+        }
+    }
+
+    #[test]
+    fn every_plugin_of_a_bundle_shares_its_library() {
+        let loader = MockLoader::default().library(
+            "pair.so",
+            &[(BUNDLE_CONSTRUCTOR, create_pair as *const c_void)],
+        );
+        let open = loader.open_count();
+        let mut manager = PluginManager::with_loader(loader);
+
+        let ids = unsafe { manager.load_library("pair.so") }.unwrap();
+        let names: Vec<&str> = ids
+            .iter()
+            .map(|&id| manager.get_plugin_by_id(id).unwrap().name())
+            .collect();
+        assert_eq!(names, ["first", "second"]);
+        assert_eq!(manager.loaded_libraries.len(), 1);
+
+        manager.unload_plugin("first").unwrap();
+        assert_eq!(open.load(Ordering::SeqCst), 1);
+        manager.unload_plugin("second").unwrap();
+        assert_eq!(open.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn a_propagated_unload_panic_still_releases_everything() {
+        let calls = calls();
+        let dropped = Arc::new(AtomicBool::new(false));
+        let mut manager = PluginManager::builder()
+            .panic_policy(PanicPolicy::Propagate)
+            .build();
+        let owner = TestPlugin {
+            service_dropped: Some(Arc::clone(&dropped)),
+            ..TestPlugin::new("owner", &calls)
+        };
+        let panicky = TestPlugin {
+            panicky: true,
+            ..TestPlugin::new("panicky", &calls)
+        };
+        manager.register_plugin(Box::new(owner)).unwrap();
+        manager.register_plugin(Box::new(panicky)).unwrap();
+
+        let unloaded = panic::catch_unwind(AssertUnwindSafe(|| manager.unload()));
+        assert!(unloaded.is_err());
+        assert!(manager.plugins.is_empty());
+        assert!(manager.loaded_libraries.is_empty());
+        assert!(dropped.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn each_plugin_logs_under_its_own_target() {
+        assert_eq!(log_target("greeter"), "plugin::greeter");
+    }
+
+    /// Lists `"exporter"` among its capabilities.
+    struct Exporter;
+
+    impl Plugin for Exporter {
+        fn name(&self) -> &'static str {
+            "exporter"
+        }
+
+        fn metadata(&self) -> PluginMetadata {
+            PluginMetadata {
+                name: self.name().to_owned(),
+                capabilities: vec!["exporter".to_owned()],
+                ..PluginMetadata::default()
+            }
+        }
+    }
+
+    #[test]
+    fn plugins_are_found_by_capability() {
+        let mut manager = manager(&["plain"], &calls());
+        manager.register_plugin(Box::new(Exporter)).unwrap();
+
+        let exporters: Vec<&str> = manager
+            .plugins_with_capability("exporter")
+            .iter()
+            .map(|p| p.name())
+            .collect();
+        assert_eq!(exporters, ["exporter"]);
+        assert!(manager.plugins_with_capability("importer").is_empty());
+    }
+
+    #[test]
+    fn is_loaded_and_len_follow_loads_and_unloads() {
+        let calls = calls();
+        let empty = PluginManager::new();
+        assert!(empty.is_empty());
+        assert_eq!(empty.len(), 0);
+
+        let mut manager = manager(&["a", "b"], &calls);
+        assert!(manager.is_loaded("a") && manager.is_loaded("b"));
+        assert!(!manager.is_loaded("c"));
+        assert_eq!(manager.len(), 2);
+        assert!(!manager.is_empty());
+
+        manager.unload_plugin("a").unwrap();
+        assert!(!manager.is_loaded("a"));
+        assert_eq!(manager.len(), 1);
+
+        manager.unload().unwrap();
+        assert!(manager.is_empty());
+    }
+
+    #[test]
+    fn a_plugin_downcasts_only_to_its_own_type() {
+        let calls = calls();
+        let mut manager = manager(&["a"], &calls);
+        assert_eq!(manager.get_plugin_as::<TestPlugin>("a").unwrap().name, "a");
+        assert!(manager.get_plugin_as::<Named>("a").is_none());
+        assert!(manager.get_plugin_as::<TestPlugin>("b").is_none());
+
+        manager
+            .get_plugin_as_mut::<TestPlugin>("a")
+            .unwrap()
+            .panicky = true;
+        assert!(manager.get_plugin_as::<TestPlugin>("a").unwrap().panicky);
+    }
+
+    #[test]
+    fn lifecycle_hooks_see_every_load_and_unload() {
+        let calls = calls();
+        let seen = calls.clone();
+        let mut manager = PluginManager::new();
+        manager.set_on_load(move |metadata, _, result| {
+            let line = format!("loaded {} {}", metadata.name, result.is_ok());
+            seen.lock().unwrap().push(line);
+        });
+        let seen = calls.clone();
+        manager.set_on_unload(move |metadata, _, result| {
+            let line = format!("unloaded {} {}", metadata.name, result.is_ok());
+            seen.lock().unwrap().push(line);
+        });
+
+        manager
+            .register_plugin(Box::new(TestPlugin::new("a", &calls)))
+            .unwrap();
+        manager.unload().unwrap();
+        assert_eq!(
+            *calls.lock().unwrap(),
+            ["loaded a true", "unload a", "unloaded a true"]
+        );
+    }
+
+    #[allow(improper_ctypes_definitions)]
+    extern "C-unwind" fn create_for_host(host_version: u32) -> *mut dyn Plugin {
+        match host_version >= 2 {
+            true => Box::into_raw(Box::new(Named("versioned"))),
+            false => std::ptr::null_mut::<Named>(),
+        }
+    }
+
+    fn versioned_manager(host_version: u32) -> PluginManager {
+        let loader = MockLoader::default().library(
+            "versioned.so",
+            &[(VERSIONED_CONSTRUCTOR, create_for_host as *const c_void)],
+        );
+        PluginManager::builder()
+            .loader(loader)
+            .host_version(host_version)
+            .build()
+    }
+
+    #[test]
+    fn a_versioned_constructor_is_told_the_host_version() {
+        let mut manager = versioned_manager(2);
+        unsafe { manager.load_plugin("versioned.so") }.unwrap();
+        assert!(manager.is_loaded("versioned"));
+    }
+
+    #[test]
+    fn a_versioned_constructor_can_decline_the_host() {
+        let mut manager = versioned_manager(1);
+        let e = unsafe { manager.load_plugin("versioned.so") }.unwrap_err();
+        assert!(
+            matches!(e.kind(), ErrorKind::IncompatibleHost(_, 1)),
+            "{}",
+            e
+        );
+        assert!(manager.is_empty());
+        assert!(manager.loaded_libraries.is_empty());
+    }
+
+    #[test]
+    fn a_registered_plugin_has_no_library() {
+        let calls = calls();
+        let mut manager = PluginManager::new();
+        let id = manager
+            .register_plugin(Box::new(TestPlugin::new("a", &calls)))
+            .unwrap();
+        assert_eq!(manager.get_plugin_by_id(id).unwrap().name(), "a");
+        assert_eq!(manager.plugin_path("a"), None);
+        assert!(manager.loaded_libraries.is_empty());
+
+        let e = unsafe { manager.get_symbol::<fn() -> u32>("a", b"answer") }.unwrap_err();
+        assert!(matches!(e.kind(), ErrorKind::SymbolNotFound(_)), "{}", e);
+
+        let e = manager
+            .register_plugin(Box::new(TestPlugin::new("a", &calls)))
+            .unwrap_err();
+        assert!(matches!(e.kind(), ErrorKind::DuplicatePlugin(_)), "{}", e);
+
+        manager.unload().unwrap();
+        assert_eq!(*calls.lock().unwrap(), ["unload a"]);
+    }
+
+    /// Takes a while to unload.
+    struct Stuck;
+
+    impl Plugin for Stuck {
+        fn name(&self) -> &'static str {
+            "stuck"
+        }
+
+        fn on_plugin_unload(&self) -> Result<()> {
+            thread::sleep(Duration::from_millis(200));
+            Ok(())
+        }
+    }
+
+    #[allow(improper_ctypes_definitions)]
+    extern "C-unwind" fn create_stuck() -> *mut dyn Plugin {
+        Box::into_raw(Box::new(Stuck))
+    }
+
+    #[test]
+    fn a_stuck_unload_times_out_and_leaks_only_its_library() {
+        let loader = MockLoader::default()
+            .library(
+                "alpha.so",
+                &[(DEFAULT_CONSTRUCTOR, create_alpha as *const c_void)],
+            )
+            .library(
+                "stuck.so",
+                &[(DEFAULT_CONSTRUCTOR, create_stuck as *const c_void)],
+            );
+        let open = loader.open_count();
+        let mut manager = PluginManager::with_loader(loader);
+        unsafe { manager.load_plugin("alpha.so") }.unwrap();
+        unsafe { manager.load_plugin("stuck.so") }.unwrap();
+
+        let failures = manager
+            .unload_with_timeout(Duration::from_millis(10))
+            .unwrap_err();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].0, "stuck");
+        assert!(
+            matches!(failures[0].1.kind(), ErrorKind::UnloadTimeout(..)),
+            "{}",
+            failures[0].1
+        );
+        assert!(manager.is_empty());
+        assert!(manager.loaded_libraries.is_empty());
+        assert_eq!(open.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn sorted_names_ignore_the_load_order() {
+        let manager = manager(&["b", "a", "C"], &calls());
+        assert_eq!(manager.sorted_plugin_names(), ["C", "a", "b"]);
+        assert!(manager.plugin_names().eq(["b", "a", "C"]));
+    }
+
+    /// Counts something, and carries the count over reloads.
+    #[derive(Default)]
+    struct Stateful {
+        count: u8,
+    }
+
+    impl Plugin for Stateful {
+        fn name(&self) -> &'static str {
+            "stateful"
+        }
+
+        fn save_state(&self) -> Option<Vec<u8>> {
+            Some(vec![self.count])
+        }
+
+        fn restore_state(&mut self, state: &[u8]) {
+            self.count = state[0];
+        }
+    }
+
+    #[allow(improper_ctypes_definitions)]
+    extern "C-unwind" fn create_stateful() -> *mut dyn Plugin {
+        Box::into_raw(Box::new(Stateful::default()))
+    }
+
+    #[test]
+    fn a_reloaded_plugin_picks_up_the_saved_state() {
+        let loader = MockLoader::default().library(
+            "stateful.so",
+            &[(DEFAULT_CONSTRUCTOR, create_stateful as *const c_void)],
+        );
+        let mut manager = PluginManager::with_loader(loader);
+        unsafe { manager.load_plugin("stateful.so") }.unwrap();
+        manager
+            .get_plugin_as_mut::<Stateful>("stateful")
+            .unwrap()
+            .count = 3;
+
+        unsafe { manager.reload_plugin("stateful", "stateful.so") }.unwrap();
+        assert_eq!(
+            manager.get_plugin_as::<Stateful>("stateful").unwrap().count,
+            3
+        );
+    }
+
+    #[test]
+    fn a_panicking_health_check_is_reported_as_unhealthy() {
+        let manager = manager(&["a", "panicky"], &calls());
+        let health = manager.health();
+        assert_eq!(health[0], ("a".to_owned(), HealthStatus::Ok));
+        assert_eq!(health[1].0, "panicky");
+        assert!(
+            matches!(&health[1].1, HealthStatus::Unhealthy(why) if why.contains("panicked")),
+            "{:?}",
+            health[1].1
+        );
+    }
+
+    /// Fails to open anything the first `failures` times it is asked to, like a file which is
+    /// still being synced.
+    struct FlakyLoader {
+        failures: AtomicUsize,
+        loader: MockLoader,
+    }
+
+    impl LibraryLoader for FlakyLoader {
+        unsafe fn load(&self, path: &OsStr) -> Result<Box<dyn LoadedLibrary>> {
+            match self
+                .failures
+                .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1))
+            {
+                Ok(_) => bail!(ErrorKind::LibraryLoadFailed(path.into())),
+                Err(_) => self.loader.load(path),
+            }
+        }
+    }
+
+    fn flaky_manager(failures: usize) -> PluginManager {
+        PluginManager::with_loader(FlakyLoader {
+            failures: AtomicUsize::new(failures),
+            loader: MockLoader::default().library(
+                "alpha.so",
+                &[(DEFAULT_CONSTRUCTOR, create_alpha as *const c_void)],
+            ),
+        })
+    }
+
+    #[test]
+    fn opening_a_library_is_retried() {
+        let mut manager = flaky_manager(2);
+        unsafe { manager.load_plugin_with_retry("alpha.so", 3, Duration::from_millis(1)) }.unwrap();
+        assert!(manager.is_loaded("alpha"));
+    }
+
+    #[test]
+    fn the_last_error_is_returned_once_the_attempts_run_out() {
+        let mut manager = flaky_manager(3);
+        let e = unsafe { manager.load_plugin_with_retry("alpha.so", 3, Duration::from_millis(1)) }
+            .unwrap_err();
+        assert!(matches!(e.kind(), ErrorKind::LibraryLoadFailed(_)), "{}", e);
+        assert!(manager.is_empty());
+    }
+
+    /// Sends a message to the other plugins once it is loaded, and again on every update.
+    #[derive(Default)]
+    struct Counter {
+        sender: Mutex<Option<MessageSender>>,
+    }
+
+    impl Plugin for Counter {
+        fn name(&self) -> &'static str {
+            "counter"
+        }
+
+        fn on_plugin_load(&self, ctx: &mut PluginContext) -> Result<()> {
+            let sender = ctx.message_sender();
+            sender.send(PluginMessage::new("metrics", *b"0"));
+            *self.sender.lock().unwrap() = Some(sender);
+            Ok(())
+        }
+
+        fn update(&mut self, _delta: Duration) {
+            let sender = self.sender.lock().unwrap();
+            sender
+                .as_ref()
+                .unwrap()
+                .send(PluginMessage::new("metrics", *b"1"));
+        }
+
+        fn receive_message(&mut self, msg: &PluginMessage) {
+            panic!("the counter was sent its own message: {:?}", msg);
+        }
+    }
+
+    /// Collects every message sent to it.
+    struct Metrics(Arc<Mutex<Vec<PluginMessage>>>);
+
+    impl Plugin for Metrics {
+        fn name(&self) -> &'static str {
+            "metrics"
+        }
+
+        fn receive_message(&mut self, msg: &PluginMessage) {
+            self.0.lock().unwrap().push(msg.clone());
+        }
+    }
+
+    #[test]
+    fn a_plugin_can_send_messages_to_another() {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let mut manager = PluginManager::builder()
+            .panic_policy(PanicPolicy::Propagate)
+            .build();
+        manager
+            .register_plugin(Box::new(Counter::default()))
+            .unwrap();
+        manager
+            .register_plugin(Box::new(Metrics(Arc::clone(&received))))
+            .unwrap();
+        assert!(received.lock().unwrap().is_empty());
+
+        manager.deliver_messages();
+        assert_eq!(
+            *received.lock().unwrap(),
+            [PluginMessage::new("metrics", *b"0")]
+        );
+
+        manager.update_all(Duration::ZERO);
+        manager.update_all(Duration::ZERO);
+        assert_eq!(
+            *received.lock().unwrap(),
+            [
+                PluginMessage::new("metrics", *b"0"),
+                PluginMessage::new("metrics", *b"1"),
+                PluginMessage::new("metrics", *b"1")
+            ]
+        );
+    }
+
+    #[allow(improper_ctypes_definitions)]
+    extern "C-unwind" fn create_counter() -> *mut dyn Plugin {
+        Box::into_raw(Box::new(Counter::default()))
+    }
+
+    #[test]
+    fn messages_sent_during_a_timed_load_are_delivered() {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let loader = MockLoader::default().library(
+            "counter.so",
+            &[(DEFAULT_CONSTRUCTOR, create_counter as *const c_void)],
+        );
+        let mut manager = PluginManager::with_loader(loader);
+        manager
+            .register_plugin(Box::new(Metrics(Arc::clone(&received))))
+            .unwrap();
+
+        unsafe { manager.load_plugin_with_timeout("counter.so", Duration::from_secs(10)) }.unwrap();
+        manager.deliver_messages();
+        assert_eq!(
+            *received.lock().unwrap(),
+            [PluginMessage::new("metrics", *b"0")]
+        );
+    }
+
+    #[test]
+    fn denied_and_unlisted_plugins_are_rejected_before_loading() {
+        let calls = calls();
+        let mut manager = PluginManager::builder()
+            .allowlist(["approved", "blocked"])
+            .denylist(["blocked"])
+            .build();
+
+        for name in ["blocked", "unlisted"] {
+            let e = manager
+                .register_plugin(Box::new(TestPlugin::new(name, &calls)))
+                .unwrap_err();
+            assert!(matches!(e.kind(), ErrorKind::PluginRejected(rejected) if rejected == name));
+        }
+        manager
+            .register_plugin(Box::new(TestPlugin::new("approved", &calls)))
+            .unwrap();
+
+        assert!(manager.plugin_names().eq(["approved"]));
+    }
+
+    #[test]
+    fn an_admission_hook_can_reject_a_plugin() {
+        let calls = calls();
+        let mut manager = PluginManager::new();
+        manager.register_hook(|plugin, library| {
+            assert!(library.is_none());
+            match plugin.name() {
+                "rejected" => bail!("not today"),
+                _ => Ok(()),
+            }
+        });
+
+        let e = manager
+            .register_plugin(Box::new(TestPlugin::new("rejected", &calls)))
+            .unwrap_err();
+        assert_eq!(e.to_string(), "not today");
+        manager
+            .register_plugin(Box::new(TestPlugin::new("accepted", &calls)))
+            .unwrap();
+        assert!(manager.plugin_names().eq(["accepted"]));
+    }
+
+    #[test]
+    fn every_plugin_can_be_called_mutably() {
+        let calls = calls();
+        let mut manager = manager(&["a", "b"], &calls);
+        for plugin in manager.plugins_mut() {
+            plugin.update(Duration::ZERO);
+        }
+        assert_eq!(*calls.lock().unwrap(), ["update a", "update b"]);
+    }
+
+    #[test]
+    fn callbacks_are_timed_by_plugin_name() {
+        let mut manager = manager(&["a"], &calls());
+        assert!(manager.timings("b").is_none());
+
+        manager.update_all(Duration::ZERO);
+        manager.update_all(Duration::ZERO);
+        manager.unload().unwrap();
+
+        let timings = manager.timings("a").unwrap();
+        assert_eq!(timings.hook("on_plugin_load").unwrap().calls, 1);
+        assert_eq!(timings.hook("update").unwrap().calls, 2);
+        assert_eq!(timings.hook("on_plugin_unload").unwrap().calls, 1);
+        assert!(timings.hook("on_event").is_none());
+    }
+
+    #[test]
+    fn a_default_manager_is_empty() {
+        assert!(PluginManager::default().is_empty());
+    }
+
+    #[test]
+    fn collecting_plugins_registers_them_and_drops_duplicates() {
+        let plugins: Vec<Box<dyn Plugin>> = vec![
+            Box::new(Named("a")),
+            Box::new(Named("b")),
+            Box::new(Named("a")),
+        ];
+        let manager: PluginManager = plugins.into_iter().collect();
+        assert!(manager.plugin_names().eq(["a", "b"]));
+    }
+
+    #[test]
+    fn plugins_are_found_by_a_predicate_over_their_metadata() {
+        let mut manager = manager(&["plain"], &calls());
+        manager.register_plugin(Box::new(Exporter)).unwrap();
+
+        let names =
+            |plugins: Vec<&dyn Plugin>| -> Vec<&str> { plugins.iter().map(|p| p.name()).collect() };
+        assert_eq!(
+            names(manager.find_plugins(|metadata| metadata.capabilities.is_empty())),
+            ["plain"]
+        );
+        assert_eq!(names(manager.find_plugins(|_| true)), ["plain", "exporter"]);
+        assert!(manager
+            .find_plugins(|metadata| metadata.author == "nobody")
+            .is_empty());
+    }
+
+    #[test]
+    fn a_cleared_plugin_is_called_again_until_it_panics_again() {
+        let calls = calls();
+        let mut manager = manager(&["first", "panicky"], &calls);
+        assert!(!manager.is_poisoned("panicky"));
+
+        manager.update_all(Duration::ZERO);
+        assert!(manager.is_poisoned("panicky"));
+        assert!(!manager.is_poisoned("first"));
+        assert!(!manager.is_poisoned("absent"));
+
+        manager.clear_poison("panicky").unwrap();
+        assert!(!manager.is_poisoned("panicky"));
+        calls.lock().unwrap().clear();
+        manager.update_all(Duration::ZERO);
+        assert_eq!(*calls.lock().unwrap(), ["update first", "update panicky"]);
+        assert!(manager.is_poisoned("panicky"));
+
+        assert!(manager.clear_poison("absent").is_err());
+    }
+
+    /// A manager with `a` loaded before `b`, and `depends` as their dependencies once loaded.
+    fn declared_after_loading(
+        calls: &Arc<Mutex<Vec<String>>>,
+        depends: &[(&str, &str)],
+    ) -> PluginManager {
+        let mut manager = manager(&["a", "b"], calls);
+        for &(name, dep) in depends {
+            let plugin: &mut TestPlugin = manager.get_plugin_as_mut(name).unwrap();
+            plugin.dependencies.push(dep.to_owned());
+        }
+        calls.lock().unwrap().clear();
+        manager
+    }
+
+    #[test]
+    fn dependents_unload_before_their_dependencies() {
+        let calls = calls();
+        // The load order alone would have `b` unloaded first.
+        let mut manager = declared_after_loading(&calls, &[("a", "b")]);
+        manager.unload().unwrap();
+        assert_eq!(*calls.lock().unwrap(), ["unload a", "unload b"]);
+    }
+
+    #[test]
+    fn a_dependency_cycle_unloads_in_reverse_load_order() {
+        let calls = calls();
+        let mut manager = declared_after_loading(&calls, &[("a", "b"), ("b", "a")]);
+        manager.unload().unwrap();
+        assert_eq!(*calls.lock().unwrap(), ["unload b", "unload a"]);
+    }
+
+    fn limited_manager(poison: bool) -> PluginManager {
+        let limits = ResourceLimits::new()
+            .max_call_duration(Duration::from_millis(50))
+            .poison_on_violation(poison);
+        let mut manager = PluginManager::builder().resource_limits(limits).build();
+        manager.register_plugin(Box::new(Slow)).unwrap();
+        manager.register_plugin(Box::new(Named("quick"))).unwrap();
+        manager
+    }
+
+    #[test]
+    fn an_overrun_is_counted_and_logged() {
+        let manager = limited_manager(false);
+        let timings = manager.timings("slow").unwrap();
+        assert_eq!(timings.hook("on_plugin_load").unwrap().over_budget, 1);
+        assert_eq!(
+            manager
+                .timings("quick")
+                .unwrap()
+                .hook("on_plugin_load")
+                .unwrap()
+                .over_budget,
+            0
+        );
+        assert!(manager.poisoned_plugins().is_empty());
+    }
+
+    #[test]
+    fn a_plugin_overrunning_its_load_is_loaded_but_poisoned() {
+        let manager = limited_manager(true);
+        assert!(manager.is_loaded("slow"));
+        assert_eq!(manager.poisoned_plugins(), ["slow"]);
+    }
+
+    #[test]
+    fn a_high_priority_plugin_unloads_last() {
+        let calls = calls();
+        let mut manager = manager(&["a", "b"], &calls);
+        manager
+            .register_plugin(Box::new(TestPlugin {
+                shutdown_priority: 10,
+                ..TestPlugin::new("logger", &calls)
+            }))
+            .unwrap();
+        calls.lock().unwrap().clear();
+
+        manager.unload().unwrap();
+        assert_eq!(
+            *calls.lock().unwrap(),
+            ["unload b", "unload a", "unload logger"]
+        );
+    }
+
+    #[test]
+    fn dependencies_outrank_shutdown_priorities() {
+        let calls = calls();
+        let mut manager = PluginManager::new();
+        // The priority alone would have `a` unloaded first.
+        manager
+            .register_plugin(Box::new(TestPlugin {
+                shutdown_priority: -1,
+                ..TestPlugin::new("a", &calls)
+            }))
+            .unwrap();
+        manager
+            .register_plugin(Box::new(TestPlugin {
+                dependencies: vec!["a".to_owned()],
+                ..TestPlugin::new("b", &calls)
+            }))
+            .unwrap();
+        calls.lock().unwrap().clear();
+
+        manager.unload().unwrap();
+        assert_eq!(*calls.lock().unwrap(), ["unload b", "unload a"]);
+    }
+
+    #[test]
+    fn globs_match_whole_file_names() {
+        assert!(matches_glob("plugin_*.so", "plugin_audio.so"));
+        assert!(matches_glob("plugin_*.so", "plugin_.so"));
+        assert!(matches_glob("plugin_?.so", "plugin_a.so"));
+        assert!(matches_glob("*", ""));
+        assert!(matches_glob("*a*b", "xxaybb"));
+
+        assert!(!matches_glob("plugin_*.so", "libhelper.so"));
+        assert!(!matches_glob("plugin_*.so", "plugin_audio.so.bak"));
+        assert!(!matches_glob("plugin_?.so", "plugin_ab.so"));
+        assert!(!matches_glob("plugin", "plugin_audio"));
+    }
+
+    #[test]
+    fn messages_go_to_the_logger_once_one_is_set() {
+        let logged = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&logged);
+        let calls = calls();
+        let mut manager = manager(&["panicky"], &calls);
+        manager.set_logger(move |level, message| {
+            sink.lock().unwrap().push((level, message.to_owned()))
+        });
+
+        manager.update_all(Duration::ZERO);
+        let logged = logged.lock().unwrap();
+        assert!(
+            logged
+                .iter()
+                .any(|(level, message)| *level == LogLevel::Error
+                    && message.starts_with("The plugin `panicky` panicked in update")),
+            "{:?}",
+            logged
+        );
+    }
+
+    #[test]
+    fn named_plugins_are_unloaded_together_and_missing_ones_reported() {
+        let loader = MockLoader::default()
+            .library(
+                "pair.so",
+                &[(BUNDLE_CONSTRUCTOR, create_pair as *const c_void)],
+            )
+            .library(
+                "alpha.so",
+                &[(DEFAULT_CONSTRUCTOR, create_alpha as *const c_void)],
+            );
+        let open = loader.open_count();
+        let mut manager = PluginManager::with_loader(loader);
+        unsafe { manager.load_library("pair.so") }.unwrap();
+        unsafe { manager.load_plugin("alpha.so") }.unwrap();
+
+        let failures = manager
+            .unload_plugins(&["alpha", "absent", "first", "absent"])
+            .unwrap_err();
+        let failed: Vec<&str> = failures.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(failed, ["absent"]);
+        assert!(matches!(failures[0].1.kind(), ErrorKind::PluginNotFound(_)));
+
+        // `second` still needs the library it shares with `first`.
+        assert!(manager.plugin_names().eq(["second"]));
+        assert_eq!(manager.loaded_library_paths(), [Path::new("pair.so")]);
+        assert_eq!(open.load(Ordering::SeqCst), 1);
+
+        manager.unload_plugins(&["second"]).unwrap();
+        assert_eq!(open.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn plugin_counts_follow_each_library() {
+        let loader = MockLoader::default()
+            .library(
+                "alpha.so",
+                &[(DEFAULT_CONSTRUCTOR, create_alpha as *const c_void)],
+            )
+            .library(
+                "pair.so",
+                &[(BUNDLE_CONSTRUCTOR, create_pair as *const c_void)],
+            );
+        let mut manager = PluginManager::with_loader(loader);
+        unsafe { manager.load_library("alpha.so") }.unwrap();
+        unsafe { manager.load_library("pair.so") }.unwrap();
+        assert_eq!(
+            manager.library_plugin_counts(),
+            [
+                (PathBuf::from("alpha.so"), 1),
+                (PathBuf::from("pair.so"), 2)
+            ]
+        );
+
+        manager.unload_plugin("alpha").unwrap();
+        manager.unload_plugin("first").unwrap();
+        assert_eq!(
+            manager.library_plugin_counts(),
+            [(PathBuf::from("pair.so"), 1)]
+        );
+    }
+
+    #[test]
+    fn drained_plugins_keep_their_libraries_open() {
+        let loader = MockLoader::default()
+            .library(
+                "stateful.so",
+                &[(DEFAULT_CONSTRUCTOR, create_stateful as *const c_void)],
+            )
+            .library(
+                "pair.so",
+                &[(BUNDLE_CONSTRUCTOR, create_pair as *const c_void)],
+            );
+        let open = loader.open_count();
+        let mut manager = PluginManager::with_loader(loader);
+        unsafe { manager.load_plugin("stateful.so") }.unwrap();
+        unsafe { manager.load_library("pair.so") }.unwrap();
+
+        let mut drained = manager.drain();
+        assert!(manager.is_empty());
+        assert!(manager.loaded_library_paths().is_empty());
+        assert_eq!(open.load(Ordering::SeqCst), 2);
+
+        let names: Vec<&str> = drained.iter().map(|p| p.name()).collect();
+        assert_eq!(names, ["stateful", "first", "second"]);
+        assert_eq!(drained.len(), 3);
+        assert!(drained.get("absent").is_none());
+        drained.get_mut("stateful").unwrap().update(Duration::ZERO);
+
+        drop(drained);
+        assert_eq!(open.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn a_drained_plugin_has_been_unloaded() {
+        let calls = calls();
+        let mut manager = manager(&["a", "b"], &calls);
+        calls.lock().unwrap().clear();
+
+        let drained = manager.drain();
+        assert_eq!(*calls.lock().unwrap(), ["unload b", "unload a"]);
+        assert!(drained.get("a").is_some());
+        assert!(manager.timings("a").is_some());
+    }
+
+    /// A plugin whose name changes once it has been loaded.
+    struct Renaming(AtomicBool);
+
+    impl Plugin for Renaming {
+        fn name(&self) -> &'static str {
+            let loaded = self.0.load(Ordering::SeqCst);
+            if loaded {
+                "after"
+            } else {
+                "before"
+            }
+        }
+
+        fn on_plugin_load(&self, _ctx: &mut PluginContext) -> Result<()> {
+            self.0.store(true, Ordering::SeqCst);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn plugins_are_known_by_the_name_they_had_when_loaded() {
+        let mut manager = PluginManager::new();
+        manager
+            .register_plugin(Box::new(Renaming(AtomicBool::new(false))))
+            .unwrap();
+
+        assert_eq!(manager.plugin_names().collect::<Vec<_>>(), ["before"]);
+        assert!(manager.is_loaded("before"));
+        assert!(!manager.is_loaded("after"));
+        manager.unload_plugin("before").unwrap();
+        assert!(manager.is_empty());
+    }
+
+    #[test]
+    fn events_only_reach_their_subscribers() {
+        let calls = calls();
+        let mut manager = PluginManager::new();
+        manager
+            .register_plugin(Box::new(TestPlugin::new("everything", &calls)))
+            .unwrap();
+        manager
+            .register_plugin(Box::new(TestPlugin {
+                subscriptions: vec!["config".to_owned()],
+                ..TestPlugin::new("config", &calls)
+            }))
+            .unwrap();
+        manager
+            .register_plugin(Box::new(TestPlugin {
+                subscriptions: vec!["login".to_owned()],
+                ..TestPlugin::new("uninterested", &calls)
+            }))
+            .unwrap();
+        calls.lock().unwrap().clear();
+
+        manager.dispatch_event(&PluginEvent::new("config"));
+        manager.unload_plugin("config").unwrap();
+        manager.dispatch_event(&PluginEvent::new("config"));
+
+        assert_eq!(
+            *calls.lock().unwrap(),
+            [
+                "event config everything",
+                "event config config",
+                "unload config",
+                "event config everything",
+            ]
+        );
+        assert_eq!(manager.subscribers("login"), [0, 1]);
+        assert!(!manager.topics.contains_key("config"));
+    }
+
+    #[test]
+    fn a_shared_library_stays_until_its_last_plugin_is_unloaded() {
+        let loader = MockLoader::default().library(
+            "pair.so",
+            &[(BUNDLE_CONSTRUCTOR, create_pair as *const c_void)],
+        );
+        let open = loader.open_count();
+        let mut manager = PluginManager::with_loader(loader);
+        unsafe { manager.load_library("pair.so") }.unwrap();
+
+        manager.unload_plugin("first").unwrap();
+        assert_eq!(
+            manager.library_plugin_counts(),
+            [(PathBuf::from("pair.so"), 1)]
+        );
+        assert_eq!(open.load(Ordering::SeqCst), 1);
+        assert_eq!(manager.get_plugin("second").unwrap().name(), "second");
+
+        manager.unload_plugin("second").unwrap();
+        assert!(manager.library_plugin_counts().is_empty());
+        assert_eq!(open.load(Ordering::SeqCst), 0);
+    }
+
+    /// Handles `count`, which counts its arguments, `fail` and `boom`, which panics.
+    struct Commander;
+
+    impl Plugin for Commander {
+        fn name(&self) -> &'static str {
+            "commander"
+        }
+
+        fn commands(&self) -> Vec<CommandSpec> {
+            vec![
+                CommandSpec::new("count", "Count the arguments").arg("[args...]"),
+                CommandSpec::new("fail", "Always fail"),
+                CommandSpec::new("boom", "Always panic"),
+            ]
+        }
+
+        fn run_command(&mut self, name: &str, args: &[String]) -> Result<i32> {
+            match name {
+                "count" => Ok(args.len() as i32),
+                "fail" => bail!("nothing to do"),
+                _ => panic!("boom"),
+            }
+        }
+    }
+
+    #[test]
+    fn commands_are_run_by_the_plugin_handling_them() {
+        let mut manager = manager(&["plain"], &calls());
+        manager.register_plugin(Box::new(Commander)).unwrap();
+
+        let commands: Vec<(String, String)> = manager
+            .all_commands()
+            .into_iter()
+            .map(|(plugin, command)| (plugin, command.name))
+            .collect();
+        assert_eq!(
+            commands,
+            [
+                ("commander".to_owned(), "count".to_owned()),
+                ("commander".to_owned(), "fail".to_owned()),
+                ("commander".to_owned(), "boom".to_owned()),
+            ]
+        );
+
+        let args = ["a".to_owned(), "b".to_owned()];
+        assert_eq!(manager.run_command("count", &args).unwrap(), 2);
+        let e = manager.run_command("missing", &[]).unwrap_err();
+        assert!(matches!(e.kind(), ErrorKind::UnknownCommand(_)), "{}", e);
+        let e = manager.run_command("fail", &[]).unwrap_err();
+        assert!(matches!(e.kind(), ErrorKind::CommandFailed(..)), "{}", e);
+    }
+
+    #[test]
+    fn a_panicking_command_poisons_its_plugin() {
+        let mut manager = PluginManager::new();
+        manager.register_plugin(Box::new(Commander)).unwrap();
+
+        let e = manager.run_command("boom", &[]).unwrap_err();
+        assert!(matches!(e.kind(), ErrorKind::PluginPanicked(..)), "{}", e);
+        assert!(manager.all_commands().is_empty());
+        let e = manager.run_command("count", &[]).unwrap_err();
+        assert!(matches!(e.kind(), ErrorKind::UnknownCommand(_)), "{}", e);
+    }
+
+    #[allow(improper_ctypes_definitions)]
+    unsafe extern "C-unwind" fn create_with_null(register: PluginRegistrar, registry: *mut c_void) {
+        register(registry, Box::into_raw(Box::new(Named("first"))));
+        register(registry, ptr::null_mut::<Named>());
+    }
+
+    #[test]
+    fn a_bundle_registering_a_null_plugin_is_rejected() {
+        let loader = MockLoader::default().library(
+            "null.so",
+            &[(BUNDLE_CONSTRUCTOR, create_with_null as *const c_void)],
+        );
+        let open = loader.open_count();
+        let mut manager = PluginManager::with_loader(loader);
+
+        let e = unsafe { manager.load_library("null.so") }.unwrap_err();
+        assert!(
+            matches!(e.kind(), ErrorKind::ConstructorReturnedNull(_)),
+            "{}",
+            e
+        );
+        assert!(manager.is_empty());
+        assert_eq!(open.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn a_snapshot_outlives_the_manager() {
+        let calls = calls();
+        let mut manager = manager(&["a", "b"], &calls);
+        manager.disable("b").unwrap();
+        manager.update_all(Duration::ZERO);
+
+        let snapshot = manager.snapshot();
+        drop(manager);
+
+        let names: Vec<&str> = snapshot.iter().map(|s| s.metadata.name.as_str()).collect();
+        assert_eq!(names, ["a", "b"]);
+        assert_eq!(snapshot[0].path, Path::new(IN_PROCESS_PATH));
+        assert!(snapshot[0].enabled && !snapshot[1].enabled);
+        assert!(!snapshot[0].poisoned);
+        assert_eq!(snapshot[0].health, HealthStatus::Ok);
+        assert_eq!(snapshot[0].timings.hook("update").unwrap().calls, 1);
+        assert!(snapshot[1].timings.hook("update").is_none());
+    }
+
+    #[cfg(all(feature = "serde", feature = "manifest"))]
+    #[test]
+    fn a_snapshot_can_be_serialized() {
+        let manager = manager(&["a"], &calls());
+        let snapshot = manager.snapshot().remove(0);
+
+        let serialized = toml::to_string(&snapshot).unwrap();
+        assert!(serialized.contains("enabled = true"), "{}", serialized);
+    }
+
+    #[test]
+    fn a_reset_manager_starts_over_but_keeps_its_ids() {
+        let calls = calls();
+        let mut manager = PluginManager::new();
+        let old = manager
+            .register_plugin(Box::new(TestPlugin::new("a", &calls)))
+            .unwrap();
+        calls.lock().unwrap().clear();
+        manager.update_all(Duration::ZERO);
+        assert!(manager.timings("a").is_some());
+
+        manager.reset().unwrap();
+        assert!(manager.is_empty());
+        assert!(manager.timings("a").is_none());
+        assert_eq!(*calls.lock().unwrap(), ["update a", "unload a"]);
+
+        let new = manager
+            .register_plugin(Box::new(TestPlugin::new("a", &calls)))
+            .unwrap();
+        assert_ne!(new, old);
+    }
+
+    #[test]
+    fn a_reset_drops_undelivered_messages() {
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let mut manager = PluginManager::new();
+        manager
+            .register_plugin(Box::new(Counter::default()))
+            .unwrap();
+
+        manager.reset().unwrap();
+        assert!(manager.is_empty());
+        manager
+            .register_plugin(Box::new(Metrics(Arc::clone(&received))))
+            .unwrap();
+        manager.deliver_messages();
+        assert!(received.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn an_interceptor_vetoes_plugins_by_their_metadata() {
+        let mut manager = PluginManager::new();
+        manager.register_interceptor(|metadata| {
+            match metadata.capabilities.iter().any(|c| c == "exporter") {
+                true => Ok(()),
+                false => Err(format!("{} exports nothing", metadata.name)),
+            }
+        });
+
+        let e = manager
+            .register_plugin(Box::new(TestPlugin::new("plain", &calls())))
+            .unwrap_err();
+        assert!(
+            matches!(
+                e.kind(),
+                ErrorKind::PluginVetoed(name, reason)
+                    if name == "plain" && reason == "plain exports nothing"
+            ),
+            "{}",
+            e
+        );
+        manager.register_plugin(Box::new(Exporter)).unwrap();
+        assert!(manager.plugin_names().eq(["exporter"]));
     }
 }
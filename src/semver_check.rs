@@ -0,0 +1,87 @@
+use crate::errors::*;
+use crate::loader::LoadedLibrary;
+use crate::plugin::PLUGIN_API_VERSION;
+use semver::Version;
+use std::path::Path;
+
+/// Check that the version of the plugin API a library was built against, if it says, is one this
+/// host provides: the same major version, and a minor version no greater than ours.
+///
+/// # Safety
+///
+/// A `_plugin_api_semver` symbol exported by the library has to be a `&str`, as
+/// [`declare_plugin!`](crate::declare_plugin) exports it.
+pub(crate) unsafe fn check(library: &dyn LoadedLibrary, path: &Path) -> Result<()> {
+    // Libraries built before the convention don't say, and are trusted as long as their ABI
+    // version matches.
+    let Ok(symbol) = library.symbol(b"_plugin_api_semver") else {
+        return Ok(());
+    };
+    let required = *(symbol as *const &str);
+    let incompatible = || {
+        ErrorKind::IncompatibleApi(
+            path.into(),
+            required.to_owned(),
+            PLUGIN_API_VERSION.to_owned(),
+        )
+    };
+
+    let plugin = Version::parse(required).chain_err(incompatible)?;
+    let host = Version::parse(PLUGIN_API_VERSION).expect("the plugin API version is valid semver");
+    if plugin.major != host.major || plugin.minor > host.minor {
+        bail!(incompatible());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::loader::mock::MockLoader;
+    use crate::loader::LibraryLoader;
+    use std::ffi::{c_void, OsStr};
+
+    static SAME: &str = PLUGIN_API_VERSION;
+    static PRERELEASE: &str = "1.0.0-alpha";
+    static NEWER_MINOR: &str = "1.1.0";
+    static NEXT_MAJOR: &str = "2.0.0";
+    static GARBLED: &str = "one point oh";
+
+    fn check_version(version: Option<&'static &'static str>) -> Result<()> {
+        let symbols: Vec<(&[u8], *const c_void)> = version
+            .map(|version| {
+                let address = version as *const &str as *const c_void;
+                (&b"_plugin_api_semver"[..], address)
+            })
+            .into_iter()
+            .collect();
+        let loader = MockLoader::default().library("plugin.so", &symbols);
+        unsafe {
+            let library = loader.load(OsStr::new("plugin.so")).unwrap();
+            check(&*library, Path::new("plugin.so"))
+        }
+    }
+
+    #[test]
+    fn the_same_major_and_no_newer_minor_is_compatible() {
+        check_version(Some(&SAME)).unwrap();
+        check_version(Some(&PRERELEASE)).unwrap();
+        check_version(None).unwrap();
+    }
+
+    #[test]
+    fn a_newer_minor_or_another_major_is_incompatible() {
+        for version in [&NEWER_MINOR, &NEXT_MAJOR, &GARBLED] {
+            let e = check_version(Some(version)).unwrap_err();
+            match e.kind() {
+                ErrorKind::IncompatibleApi(_, required, host) => {
+                    assert_eq!(
+                        (required.as_str(), host.as_str()),
+                        (*version, PLUGIN_API_VERSION)
+                    )
+                }
+                kind => panic!("expected an incompatible API, got {:?}", kind),
+            }
+        }
+    }
+}
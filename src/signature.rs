@@ -0,0 +1,44 @@
+use crate::errors::*;
+use crate::plugin_manager::{PluginId, PluginManager};
+use ed25519_dalek::{Signature, VerifyingKey};
+use std::ffi::OsStr;
+
+impl PluginManager {
+    /// Load a single plugin like [`PluginManager::load_plugin`], but only once the library has
+    /// been checked against `signature`, a detached ed25519 signature of the whole file made with
+    /// the private half of `public_key`. If the signature doesn't check out (or isn't 64 bytes
+    /// long), a `SignatureInvalid` error is returned and the library is never opened, so none of
+    /// its code gets to run.
+    ///
+    /// The file is only read once, and the library is loaded from the bytes which were checked
+    /// (see [`PluginManager::load_plugin_from_bytes`]).
+    ///
+    /// ```no_run
+    /// use plugin_framework::{PluginManager, VerifyingKey};
+    /// use std::fs;
+    ///
+    /// let key = VerifyingKey::from_bytes(&[0; 32]).unwrap();
+    /// let signature = fs::read("plugins/libgreeter.so.sig").unwrap();
+    ///
+    /// let mut manager = PluginManager::new();
+    /// unsafe { manager.load_plugin_signed("plugins/libgreeter.so", &signature, &key) }.unwrap();
+    /// ```
+    ///
+    /// # Safety
+    ///
+    /// The same requirements as [`PluginManager::load_plugin`] apply.
+    pub unsafe fn load_plugin_signed<P: AsRef<OsStr>>(
+        &mut self,
+        filename: P,
+        signature: &[u8],
+        public_key: &VerifyingKey,
+    ) -> Result<PluginId> {
+        self.load_plugin_checked(filename.as_ref(), |path, contents| {
+            let signature = Signature::from_slice(signature)
+                .chain_err(|| ErrorKind::SignatureInvalid(path.into()))?;
+            public_key
+                .verify_strict(contents, &signature)
+                .chain_err(|| ErrorKind::SignatureInvalid(path.into()))
+        })
+    }
+}
@@ -0,0 +1,26 @@
+use crate::health::HealthStatus;
+use crate::metadata::PluginMetadata;
+use crate::plugin_manager::PluginId;
+use crate::timings::PluginTimings;
+use std::path::PathBuf;
+
+/// Everything a [`PluginManager`](crate::PluginManager) knows about one of its plugins at a given
+/// moment, as returned by [`PluginManager::snapshot`](crate::PluginManager::snapshot), e.g. for a
+/// status endpoint.
+///
+/// Everything in here is owned, so a snapshot can be kept around (or sent elsewhere) after the
+/// manager, or the lock on it, has been let go. With the `serde` feature enabled, it can be
+/// serialized too.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PluginSnapshot {
+    pub id: PluginId,
+    pub metadata: PluginMetadata,
+    /// The file the plugin was loaded from, as described by
+    /// [`PluginManager::plugin_path`](crate::PluginManager::plugin_path).
+    pub path: PathBuf,
+    pub enabled: bool,
+    pub poisoned: bool,
+    pub health: HealthStatus,
+    pub timings: PluginTimings,
+}
@@ -0,0 +1,52 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// How long a plugin has spent in each of its callbacks, as returned by
+/// [`PluginManager::timings`](crate::PluginManager::timings).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PluginTimings {
+    hooks: HashMap<&'static str, HookTimings>,
+}
+
+impl PluginTimings {
+    /// Get the timings of one of the plugin's callbacks, by the name of the `Plugin` method (e.g.
+    /// `"on_plugin_load"`, `"update"` or `"on_plugin_unload"`). Returns `None` if it has never
+    /// been called.
+    pub fn hook(&self, name: &str) -> Option<HookTimings> {
+        self.hooks.get(name).copied()
+    }
+
+    pub(crate) fn record(&mut self, hook: &'static str, elapsed: Duration, over_budget: bool) {
+        let timings = self.hooks.entry(hook).or_default();
+        timings.calls += 1;
+        timings.last = elapsed;
+        timings.total += elapsed;
+        timings.over_budget += u64::from(over_budget);
+    }
+}
+
+/// How long a plugin has spent in one of its callbacks.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct HookTimings {
+    /// How many calls were timed.
+    pub calls: u64,
+    /// How long the most recent call took.
+    pub last: Duration,
+    /// How long every call took altogether.
+    pub total: Duration,
+    /// How many calls took longer than the manager's
+    /// [`ResourceLimits`](crate::ResourceLimits) allow.
+    pub over_budget: u64,
+}
+
+impl HookTimings {
+    /// How long a call took on average.
+    pub fn average(&self) -> Duration {
+        match self.calls {
+            0 => Duration::ZERO,
+            calls => Duration::from_nanos((self.total.as_nanos() / u128::from(calls)) as u64),
+        }
+    }
+}
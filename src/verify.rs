@@ -0,0 +1,42 @@
+use crate::errors::*;
+use crate::plugin_manager::{PluginId, PluginManager};
+use sha2::{Digest, Sha256};
+use std::ffi::OsStr;
+use std::fmt::Write;
+
+impl PluginManager {
+    /// Load a single plugin like [`PluginManager::load_plugin`], but only once the library's
+    /// SHA-256 checksum has been checked against `expected_sha256` (given in hex). If it doesn't
+    /// match, an `IntegrityCheckFailed` error is returned and the library is never opened, so
+    /// none of its code gets to run.
+    ///
+    /// The file is only read once, and the library is loaded from the bytes which were hashed (see
+    /// [`PluginManager::load_plugin_from_bytes`]).
+    ///
+    /// # Safety
+    ///
+    /// The same requirements as [`PluginManager::load_plugin`] apply.
+    pub unsafe fn load_plugin_verified<P: AsRef<OsStr>>(
+        &mut self,
+        filename: P,
+        expected_sha256: &str,
+    ) -> Result<PluginId> {
+        self.load_plugin_checked(filename.as_ref(), |path, contents| {
+            let found = Sha256::digest(contents)
+                .iter()
+                .fold(String::new(), |mut hex, byte| {
+                    let _ = write!(hex, "{:02x}", byte);
+                    hex
+                });
+            let expected = expected_sha256.trim();
+            if !found.eq_ignore_ascii_case(expected) {
+                bail!(ErrorKind::IntegrityCheckFailed(
+                    path.into(),
+                    expected.to_owned(),
+                    found
+                ));
+            }
+            Ok(())
+        })
+    }
+}
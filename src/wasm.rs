@@ -0,0 +1,147 @@
+use crate::context::PluginContext;
+use crate::errors::*;
+use crate::loader::LoadedLibrary;
+use crate::plugin::Plugin;
+use std::any::Any;
+use std::ffi::{c_void, OsStr};
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use wasmtime::{Engine, Instance, Memory, Module, Store, TypedFunc};
+
+/// The extension WebAssembly plugins carry.
+pub(crate) const WASM_EXTENSION: &str = "wasm";
+
+/// Every WebAssembly plugin is compiled and run by the same engine.
+fn engine() -> &'static Engine {
+    static ENGINE: OnceLock<Engine> = OnceLock::new();
+    ENGINE.get_or_init(Engine::default)
+}
+
+/// Turn an error from `wasmtime` into one of ours, keeping its whole chain of causes.
+fn wasm_error(e: wasmtime::Error) -> Error {
+    Error::from(format!("{:#}", e))
+}
+
+/// A compiled WebAssembly module, standing in for the shared library native plugins are created
+/// from. It doesn't export any native symbols.
+pub(crate) struct WasmLibrary {
+    module: Module,
+    /// The name of the plugin instantiated from the module, which lives here (like a native
+    /// plugin's name lives in its library) so that it outlives the plugin.
+    name: OnceLock<Box<str>>,
+}
+
+impl LoadedLibrary for WasmLibrary {
+    unsafe fn symbol(&self, name: &[u8]) -> Result<*const c_void> {
+        bail!(ErrorKind::SymbolNotFound(
+            String::from_utf8_lossy(name).into_owned()
+        ))
+    }
+}
+
+/// Check whether `path` names a WebAssembly plugin, based purely on its extension.
+pub(crate) fn is_wasm(path: &Path) -> bool {
+    path.extension() == Some(OsStr::new(WASM_EXTENSION))
+}
+
+/// Compile the WebAssembly module at `path`. Nothing in it is run yet.
+pub(crate) fn open(path: &Path) -> Result<Box<dyn LoadedLibrary>> {
+    let module = Module::from_file(engine(), path)
+        .map_err(wasm_error)
+        .chain_err(|| ErrorKind::LibraryLoadFailed(path.into()))?;
+    Ok(Box::new(WasmLibrary {
+        module,
+        name: OnceLock::new(),
+    }))
+}
+
+/// Instantiate the plugin in `library`, if it is a WebAssembly module rather than a native
+/// library.
+pub(crate) fn instantiate(
+    library: &dyn LoadedLibrary,
+    path: &Path,
+) -> Option<Result<Box<dyn Plugin>>> {
+    let library = (library as &dyn Any).downcast_ref::<WasmLibrary>()?;
+    Some(
+        WasmPlugin::new(library)
+            .map(|plugin| Box::new(plugin) as Box<dyn Plugin>)
+            .chain_err(|| ErrorKind::LibraryLoadFailed(path.into())),
+    )
+}
+
+/// A plugin running inside a WebAssembly sandbox, bridging the [`Plugin`] callbacks to the
+/// functions its module exports.
+struct WasmPlugin {
+    name: &'static str,
+    instance: Mutex<(Store<()>, Instance)>,
+}
+
+impl WasmPlugin {
+    fn new(library: &WasmLibrary) -> Result<Self> {
+        let mut store = Store::new(engine(), ());
+        // Plugins get no imports, so they can't reach anything outside their own memory.
+        let instance = Instance::new(&mut store, &library.module, &[]).map_err(wasm_error)?;
+
+        let memory: Memory = match instance.get_memory(&mut store, "memory") {
+            Some(memory) => memory,
+            None => bail!(ErrorKind::SymbolNotFound("memory".to_owned())),
+        };
+        let plugin_name: TypedFunc<(), i64> = instance
+            .get_typed_func(&mut store, "plugin_name")
+            .map_err(wasm_error)
+            .chain_err(|| ErrorKind::SymbolNotFound("plugin_name".to_owned()))?;
+
+        // The name is returned as its address in the module's memory, and its length, packed
+        // into the upper and lower halves of an `i64`.
+        let packed = plugin_name.call(&mut store, ()).map_err(wasm_error)? as u64;
+        let (start, len) = ((packed >> 32) as usize, (packed as u32) as usize);
+        let bytes = memory
+            .data(&store)
+            .get(start..start + len)
+            .ok_or("the plugin's name isn't inside its memory")?;
+        let name = std::str::from_utf8(bytes).chain_err(|| "the plugin's name isn't UTF-8")?;
+
+        let name: &str = library.name.get_or_init(|| name.into());
+        // The name lives in the library, which the manager keeps around for longer than the
+        // plugin, the same as the names of native plugins.
+        let name = unsafe { &*(name as *const str) };
+
+        Ok(WasmPlugin {
+            name,
+            instance: Mutex::new((store, instance)),
+        })
+    }
+
+    /// Call one of the callbacks the module may export, which take no arguments and return zero
+    /// on success. Callbacks the module doesn't export succeed without doing anything.
+    fn call(&self, callback: &str) -> Result<()> {
+        let mut guard = self.instance.lock().unwrap_or_else(|e| e.into_inner());
+        let (store, instance) = &mut *guard;
+
+        let Some(func) = instance.get_func(&mut *store, callback) else {
+            return Ok(());
+        };
+        let code = func
+            .typed::<(), i32>(&*store)
+            .and_then(|func| func.call(&mut *store, ()))
+            .map_err(wasm_error)?;
+        match code {
+            0 => Ok(()),
+            code => bail!("{} returned {}", callback, code),
+        }
+    }
+}
+
+impl Plugin for WasmPlugin {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn on_plugin_load(&self, _ctx: &mut PluginContext) -> Result<()> {
+        self.call("on_plugin_load")
+    }
+
+    fn on_plugin_unload(&self) -> Result<()> {
+        self.call("on_plugin_unload")
+    }
+}
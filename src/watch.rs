@@ -0,0 +1,205 @@
+use crate::errors::*;
+use crate::logging::{debug, trace, warn, Logger};
+use crate::plugin_manager::{is_plugin_library, LoadReport, PluginManager};
+use notify::{Event, RecursiveMode, Watcher};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How long a file has to go without being touched before we act on it. Compilers and editors
+/// tend to write a file in several steps (or write a temporary file and rename it into place), and
+/// loading a library halfway through that would fail.
+const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// A filesystem watcher, along with the changes it has seen which haven't been applied yet.
+pub(crate) struct PluginWatcher {
+    watcher: notify::RecommendedWatcher,
+    /// Every changed path, along with when it was last touched.
+    pending: Arc<Mutex<HashMap<PathBuf, Instant>>>,
+}
+
+impl PluginWatcher {
+    fn new(dir: &Path, logger: Logger) -> Result<Self> {
+        let pending = Arc::new(Mutex::new(HashMap::new()));
+        let sink = Arc::clone(&pending);
+
+        let watcher =
+            notify::recommended_watcher(move |event: notify::Result<Event>| match event {
+                Ok(event) => {
+                    let now = Instant::now();
+                    let mut pending = sink.lock().unwrap_or_else(|e| e.into_inner());
+                    for path in event.paths {
+                        pending.insert(path, now);
+                    }
+                }
+                Err(e) => warn!(logger, "Error watching the plugin directory: {}", e),
+            })
+            .chain_err(|| ErrorKind::WatchFailed(dir.into()))?;
+
+        Ok(Self { watcher, pending })
+    }
+
+    /// Take every pending path which has settled down, i.e. hasn't changed for a while.
+    fn settled(&self) -> Vec<PathBuf> {
+        let mut pending = self.pending.lock().unwrap_or_else(|e| e.into_inner());
+        let settled: Vec<PathBuf> = pending
+            .iter()
+            .filter(|(_, touched)| touched.elapsed() >= DEBOUNCE)
+            .map(|(path, _)| path.clone())
+            .collect();
+
+        for path in &settled {
+            pending.remove(path);
+        }
+
+        settled
+    }
+}
+
+impl PluginManager {
+    /// Start watching a folder of plugins for changes, so they can be applied with
+    /// [`PluginManager::apply_watched_changes`]. This can be called several times to watch more
+    /// than one folder. Only the folder itself is watched, not its subfolders.
+    ///
+    /// This is meant for development, where plugins are rebuilt constantly and restarting the
+    /// host every time would be painful.
+    pub fn watch<P: AsRef<Path>>(&mut self, dir: P) -> Result<()> {
+        let dir = dir.as_ref();
+
+        if self.watcher.is_none() {
+            self.watcher = Some(PluginWatcher::new(dir, self.logger.clone())?);
+        }
+        let watcher = self.watcher.as_mut().unwrap();
+
+        watcher
+            .watcher
+            .watch(dir, RecursiveMode::NonRecursive)
+            .chain_err(|| ErrorKind::WatchFailed(dir.into()))?;
+        debug!(self.logger, "Watching {:?} for plugin changes", dir);
+
+        Ok(())
+    }
+
+    /// Apply the changes seen in the folders being [watched](PluginManager::watch) since the last
+    /// call. This should be called periodically, e.g. from the host's main loop.
+    ///
+    /// A file is only acted on once it has settled (gone half a second without being touched),
+    /// so a library which is still being written, or a save which writes a temporary file and
+    /// renames it into place, doesn't trigger a failed load. Each settled file is then compared
+    /// against what is on disk:
+    ///
+    /// - a modified library backing a loaded plugin is reloaded with
+    ///   [`PluginManager::reload_plugin`],
+    /// - a new library is loaded with [`PluginManager::load_plugin`],
+    /// - a library which was removed has its plugins unloaded.
+    ///
+    /// The returned [`LoadReport`] lists every library which was (re)loaded or failed to be.
+    ///
+    /// # Safety
+    ///
+    /// New and changed libraries are loaded as if by [`PluginManager::load_plugin`], so the same
+    /// requirements apply to every file appearing in a watched folder.
+    pub unsafe fn apply_watched_changes(&mut self) -> LoadReport {
+        let mut report = LoadReport::default();
+
+        let settled = match &self.watcher {
+            Some(watcher) => watcher.settled(),
+            None => return report,
+        };
+
+        for path in settled {
+            if !is_plugin_library(&path) {
+                trace!(self.logger, "Ignoring change to {:?}", path);
+                continue;
+            }
+
+            let loaded = self.plugin_from_path(&path).map(str::to_owned);
+            let outcome = match (loaded, path.is_file()) {
+                (Some(name), true) => {
+                    debug!(self.logger, "{:?} changed, reloading `{}`", path, name);
+                    self.reload_plugin(&name, &path).map(drop)
+                }
+                (None, true) => {
+                    debug!(self.logger, "{:?} appeared, loading it", path);
+                    self.load_plugin(&path).map(drop)
+                }
+                (Some(_), false) => {
+                    // A library may have held several plugins, so unload all of them.
+                    while let Some(name) = self.plugin_from_path(&path).map(str::to_owned) {
+                        debug!(self.logger, "{:?} was removed, unloading `{}`", path, name);
+                        if let Err(e) = self.unload_plugin(&name) {
+                            report.failed.push((path.clone(), e));
+                        }
+                    }
+                    continue;
+                }
+                (None, false) => continue,
+            };
+
+            match outcome {
+                Ok(()) => {
+                    report
+                        .plugins
+                        .extend(self.plugins_from_path(&path).into_iter().map(str::to_owned));
+                    report.loaded.push(path);
+                }
+                Err(e) => report.failed.push((path, e)),
+            }
+        }
+
+        report
+    }
+
+    /// Find the name of the plugin loaded from `path`, comparing canonicalized paths since the
+    /// watcher and the host may spell the same file differently.
+    fn plugin_from_path(&self, path: &Path) -> Option<&str> {
+        self.plugins_from_path(path).into_iter().next()
+    }
+
+    /// Find the names of every plugin loaded from `path`, as for
+    /// [`plugin_from_path`](Self::plugin_from_path).
+    fn plugins_from_path(&self, path: &Path) -> Vec<&str> {
+        let Some(path) = canonical(path) else {
+            return Vec::new();
+        };
+        self.plugin_names()
+            .zip(self.plugin_paths())
+            .filter(|(_, p)| canonical(p).as_ref() == Some(&path))
+            .map(|(name, _)| name)
+            .collect()
+    }
+}
+
+/// Canonicalize `path`. A library which was just removed can't be canonicalized itself, so its
+/// folder is instead, which still tells whether it is the same file as a loaded library.
+fn canonical(path: &Path) -> Option<PathBuf> {
+    fs::canonicalize(path).ok().or_else(|| {
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        Some(fs::canonicalize(dir).ok()?.join(path.file_name()?))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn removed_files_canonicalize_through_their_folder() {
+        let dir = std::env::temp_dir().join(format!("plugin_watch_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("libgone.so");
+        fs::write(&file, b"").unwrap();
+
+        let before = canonical(&file).unwrap();
+        fs::remove_file(&file).unwrap();
+        let after = canonical(&dir.join(".").join("libgone.so")).unwrap();
+
+        assert_eq!(before, after);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
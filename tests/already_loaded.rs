@@ -0,0 +1,46 @@
+mod common;
+
+use plugin_framework::{ErrorKind, PluginConfig, PluginManager};
+
+#[test]
+fn loading_the_same_file_twice_is_an_error() {
+    let hello = common::fixture("hello");
+    let mut manager = PluginManager::new();
+    unsafe { manager.load_plugin(&hello) }.unwrap();
+
+    // The same file, spelled differently.
+    let respelled = hello
+        .parent()
+        .unwrap()
+        .join(".")
+        .join(hello.file_name().unwrap());
+    let e = unsafe { manager.load_plugin(&respelled) }.unwrap_err();
+    assert!(matches!(e.kind(), ErrorKind::AlreadyLoaded(_)), "{}", e);
+
+    let e =
+        unsafe { manager.load_plugin_with_config(&hello, &PluginConfig::default()) }.unwrap_err();
+    assert!(matches!(e.kind(), ErrorKind::AlreadyLoaded(_)), "{}", e);
+    assert_eq!(manager.len(), 1);
+}
+
+#[test]
+fn loading_the_same_file_twice_is_an_error_even_with_duplicate_names() {
+    let hello = common::fixture("hello");
+    let mut manager = PluginManager::builder().allow_duplicate_names(true).build();
+    unsafe { manager.load_plugin(&hello) }.unwrap();
+
+    let e = unsafe { manager.load_plugin(&hello) }.unwrap_err();
+    assert!(matches!(e.kind(), ErrorKind::AlreadyLoaded(_)), "{}", e);
+    assert_eq!(manager.len(), 1);
+}
+
+#[test]
+fn an_ignored_second_load_hands_back_the_loaded_plugin() {
+    let hello = common::fixture("hello");
+    let mut manager = PluginManager::builder().ignore_already_loaded(true).build();
+    let first = unsafe { manager.load_plugin(&hello) }.unwrap();
+
+    let second = unsafe { manager.load_plugin_with_retry(&hello, 1, Default::default()) }.unwrap();
+    assert_eq!(first, second);
+    assert_eq!(manager.len(), 1);
+}
@@ -0,0 +1,46 @@
+//! Helpers shared by the integration tests.
+
+// Each test binary only uses some of these.
+#![allow(dead_code)]
+
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::OnceLock;
+
+/// The path to the plugin library built from the fixture crate called `name`, under
+/// `tests/fixtures`. Every fixture is built the first time one is asked for.
+pub fn fixture(name: &str) -> PathBuf {
+    static BUILT: OnceLock<PathBuf> = OnceLock::new();
+    let dir = BUILT.get_or_init(|| {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let target = root.join("target").join("fixtures");
+        let status = Command::new(env::var_os("CARGO").unwrap_or_else(|| "cargo".into()))
+            .arg("build")
+            .arg("--quiet")
+            .arg("--manifest-path")
+            .arg(root.join("tests").join("fixtures").join("Cargo.toml"))
+            .arg("--target-dir")
+            .arg(&target)
+            .status()
+            .expect("cargo can be run to build the fixtures");
+        assert!(status.success(), "the fixtures failed to build");
+        target.join("debug")
+    });
+
+    let file = format!(
+        "{}{}{}",
+        env::consts::DLL_PREFIX,
+        name,
+        env::consts::DLL_SUFFIX
+    );
+    dir.join(file)
+}
+
+/// A fresh, empty folder for a test to put files in, named after the test.
+pub fn temp_dir(test: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("plugin_framework_{}_{}", test, std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    dir
+}
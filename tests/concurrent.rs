@@ -0,0 +1,111 @@
+mod common;
+
+use plugin_framework::{
+    ConcurrentPluginManager, Plugin, PluginContext, PluginManager, PluginMessage, Result,
+};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+fn shared_hello() -> Arc<ConcurrentPluginManager> {
+    let manager = Arc::new(ConcurrentPluginManager::default());
+    unsafe { manager.load_plugin(common::fixture("hello")) }.unwrap();
+    manager
+}
+
+#[test]
+fn plugins_can_be_looked_up_while_a_shared_lock_is_held() {
+    let manager = shared_hello();
+    let held = manager.read();
+
+    let reader = Arc::clone(&manager);
+    let (names, found) = thread::spawn(move || {
+        let found = reader.with_plugin("hello", |p| p.name().to_owned());
+        (reader.plugin_names(), found)
+    })
+    .join()
+    .unwrap();
+    assert_eq!(names, ["hello"]);
+    assert_eq!(found.as_deref(), Some("hello"));
+
+    drop(held);
+    assert!(manager.with_plugin("absent", |p| p.name()).is_none());
+}
+
+#[test]
+fn plugins_loaded_on_one_thread_are_seen_on_another() {
+    let manager = Arc::new(ConcurrentPluginManager::from(PluginManager::new()));
+    assert!(manager.is_empty());
+
+    let loader = Arc::clone(&manager);
+    thread::spawn(move || unsafe { loader.load_plugin(common::fixture("hello")) }.unwrap())
+        .join()
+        .unwrap();
+    assert!(manager.is_loaded("hello"));
+    assert_eq!(manager.len(), 1);
+
+    manager.unload_plugin("hello").unwrap();
+    assert!(manager.is_empty());
+}
+
+#[test]
+fn a_panic_while_holding_the_lock_doesnt_poison_it() {
+    let manager = shared_hello();
+    let panicked = panic::catch_unwind(AssertUnwindSafe(|| {
+        let _guard = manager.write();
+        panic!("the host panicked");
+    }));
+    assert!(panicked.is_err());
+
+    assert_eq!(manager.plugin_names(), ["hello"]);
+    let manager = Arc::try_unwrap(manager).ok().unwrap().into_inner();
+    assert!(manager.is_loaded("hello"));
+}
+
+/// Sends a message while it is being loaded.
+struct Greeter;
+
+impl Plugin for Greeter {
+    fn name(&self) -> &'static str {
+        "greeter"
+    }
+
+    fn on_plugin_load(&self, ctx: &mut PluginContext) -> Result<()> {
+        ctx.message_sender()
+            .send(PluginMessage::new("greeting", *b"hi"));
+        Ok(())
+    }
+}
+
+/// Collects every message sent to it.
+struct Listener(Arc<Mutex<Vec<PluginMessage>>>);
+
+impl Plugin for Listener {
+    fn name(&self) -> &'static str {
+        "listener"
+    }
+
+    fn receive_message(&mut self, msg: &PluginMessage) {
+        self.0.lock().unwrap().push(msg.clone());
+    }
+}
+
+#[test]
+fn messages_sent_while_loading_are_delivered_on_request() {
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let manager = ConcurrentPluginManager::default();
+    {
+        let mut inner = manager.write();
+        inner
+            .register_plugin(Box::new(Listener(Arc::clone(&received))))
+            .unwrap();
+        inner.register_plugin(Box::new(Greeter)).unwrap();
+    }
+    assert!(received.lock().unwrap().is_empty());
+
+    manager.deliver_messages();
+    assert_eq!(
+        *received.lock().unwrap(),
+        [PluginMessage::new("greeting", *b"hi")]
+    );
+}
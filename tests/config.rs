@@ -0,0 +1,33 @@
+mod common;
+
+use plugin_framework::{PluginConfig, PluginManager};
+
+#[test]
+fn the_config_reaches_the_plugins_constructor() {
+    let mut manager = PluginManager::new();
+    let config = PluginConfig::new().with("version", "2.0");
+    let id =
+        unsafe { manager.load_plugin_with_config(common::fixture("configured"), &config) }.unwrap();
+
+    assert_eq!(manager.get_plugin_by_id(id).unwrap().version(), "2.0");
+}
+
+#[test]
+fn a_plain_load_uses_the_plain_constructor() {
+    let mut manager = PluginManager::new();
+    let id = unsafe { manager.load_plugin(common::fixture("configured")) }.unwrap();
+
+    assert_eq!(
+        manager.get_plugin_by_id(id).unwrap().version(),
+        "unconfigured"
+    );
+}
+
+#[test]
+fn a_library_without_a_config_constructor_ignores_the_config() {
+    let mut manager = PluginManager::new();
+    let config = PluginConfig::new().with("version", "2.0");
+    let id = unsafe { manager.load_plugin_with_config(common::fixture("hello"), &config) }.unwrap();
+
+    assert_eq!(manager.get_plugin_by_id(id).unwrap().name(), "hello");
+}
@@ -0,0 +1,35 @@
+mod common;
+
+use plugin_framework::PluginManager;
+
+#[test]
+fn a_hand_written_constructor_loads() {
+    let mut manager = PluginManager::new();
+    let id = unsafe { manager.load_plugin(common::fixture("raw_constructor")) }.unwrap();
+    assert_eq!(
+        manager.get_plugin_by_id(id).unwrap().name(),
+        "raw_constructor"
+    );
+
+    manager.unload().unwrap();
+    assert!(manager.loaded_library_paths().is_empty());
+}
+
+#[test]
+fn a_declared_plugin_loads() {
+    let mut manager = PluginManager::new();
+    let id = unsafe { manager.load_plugin(common::fixture("hello")) }.unwrap();
+    assert_eq!(manager.get_plugin_by_id(id).unwrap().name(), "hello");
+}
+
+#[test]
+fn every_plugin_of_a_declared_bundle_loads() {
+    let mut manager = PluginManager::new();
+    let ids = unsafe { manager.load_library(common::fixture("bundle")) }.unwrap();
+    let names: Vec<&str> = ids
+        .iter()
+        .map(|&id| manager.get_plugin_by_id(id).unwrap().name())
+        .collect();
+    assert_eq!(names, ["first", "second"]);
+    assert_eq!(manager.loaded_library_paths().len(), 1);
+}
@@ -0,0 +1,20 @@
+mod common;
+
+use plugin_framework::{ErrorKind, PluginManager};
+
+#[test]
+fn a_constructor_is_looked_up_under_the_given_symbol() {
+    let mut manager = PluginManager::new();
+    let hello = common::fixture("hello");
+
+    let e = unsafe { manager.load_plugin_with_symbol(&hello, b"_hello_create") }.unwrap_err();
+    assert!(
+        matches!(e.kind(), ErrorKind::SymbolNotFound(symbol) if symbol == "_hello_create"),
+        "{}",
+        e
+    );
+    assert!(manager.is_empty());
+
+    unsafe { manager.load_plugin_with_symbol(&hello, b"_plugin_create") }.unwrap();
+    assert!(manager.plugin_names().eq(["hello"]));
+}
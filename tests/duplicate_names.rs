@@ -0,0 +1,42 @@
+mod common;
+
+use plugin_framework::{ErrorKind, PluginManager};
+use std::fs;
+use std::path::PathBuf;
+
+/// A copy of the `hello` fixture, so the same plugin can be loaded from a second file.
+fn second_hello(test: &str) -> (PathBuf, PathBuf) {
+    let hello = common::fixture("hello");
+    let dir = common::temp_dir(test);
+    let copy = dir.join(hello.file_name().unwrap());
+    fs::copy(&hello, &copy).unwrap();
+    (dir, copy)
+}
+
+#[test]
+fn a_second_plugin_with_a_taken_name_is_rejected() {
+    let (dir, copy) = second_hello("taken_name");
+    let mut manager = PluginManager::new();
+    unsafe { manager.load_plugin(common::fixture("hello")) }.unwrap();
+
+    let e = unsafe { manager.load_plugin(&copy) }.unwrap_err();
+    assert!(matches!(e.kind(), ErrorKind::DuplicatePlugin(_)), "{}", e);
+    assert!(manager.plugin_names().eq(["hello"]));
+
+    drop(manager);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn duplicate_names_can_be_allowed() {
+    let (dir, copy) = second_hello("duplicate_names");
+    let mut manager = PluginManager::new();
+    manager.set_allow_duplicate_names(true);
+    unsafe { manager.load_plugin(common::fixture("hello")) }.unwrap();
+    unsafe { manager.load_plugin(&copy) }.unwrap();
+
+    assert!(manager.plugin_names().eq(["hello", "hello"]));
+
+    drop(manager);
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,75 @@
+mod common;
+
+use plugin_framework::{Error, ErrorKind, PluginManager};
+use std::fs;
+
+/// A manager with the `hello` fixture loaded, so there is something for a failed load to disturb.
+fn manager() -> PluginManager {
+    let mut manager = PluginManager::new();
+    unsafe { manager.try_load_plugin(common::fixture("hello")) }.unwrap();
+    manager
+}
+
+/// Load the fixture called `name` into `manager`, which is expected to fail, and check the manager
+/// still only has `hello` loaded. Returns the error.
+fn load_failing(manager: &mut PluginManager, name: &str) -> Error {
+    let e = unsafe { manager.try_load_plugin(common::fixture(name)) }.unwrap_err();
+
+    assert!(manager.plugin_names().eq(["hello"]));
+    assert_eq!(manager.loaded_library_paths(), [common::fixture("hello")]);
+    e
+}
+
+#[test]
+fn a_missing_constructor_changes_nothing() {
+    let e = load_failing(&mut manager(), "missing_symbol");
+    assert!(matches!(e.kind(), ErrorKind::SymbolNotFound(_)), "{}", e);
+}
+
+#[test]
+fn an_abi_mismatch_changes_nothing() {
+    let e = load_failing(&mut manager(), "abi_mismatch");
+    assert!(matches!(e.kind(), ErrorKind::AbiMismatch(..)), "{}", e);
+}
+
+#[test]
+fn a_failing_load_hook_changes_nothing() {
+    let mut manager = manager();
+    let e = load_failing(&mut manager, "failing_load");
+    assert!(matches!(e.kind(), ErrorKind::PluginLoadFailed(..)), "{}", e);
+    assert!(manager.timings("failing_load").is_none());
+}
+
+#[test]
+fn a_duplicate_name_changes_nothing() {
+    // A second file, since loading the same one again is rejected before its name is checked.
+    let dir = common::temp_dir("failed_duplicate");
+    let hello = common::fixture("hello");
+    let copy = dir.join(hello.file_name().unwrap());
+    fs::copy(&hello, &copy).unwrap();
+
+    let mut manager = PluginManager::new();
+    let id = unsafe { manager.try_load_plugin(&hello) }.unwrap();
+    let e = unsafe { manager.try_load_plugin(&copy) }.unwrap_err();
+    assert!(matches!(e.kind(), ErrorKind::DuplicatePlugin(_)), "{}", e);
+    assert!(manager.plugin_names().eq(["hello"]));
+    assert_eq!(manager.loaded_library_paths(), [hello]);
+    assert_eq!(
+        manager.get_plugin_by_id(id).map(|p| p.name()),
+        Some("hello")
+    );
+
+    drop(manager);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn a_failed_load_uses_up_no_id() {
+    let mut expected = manager();
+    let next = unsafe { expected.try_load_plugin(common::fixture("configured")) }.unwrap();
+
+    let mut manager = manager();
+    load_failing(&mut manager, "failing_load");
+    let id = unsafe { manager.try_load_plugin(common::fixture("configured")) }.unwrap();
+    assert_eq!(id, next);
+}
@@ -0,0 +1,21 @@
+//! A plugin claiming to be built against a different ABI version than the host's.
+
+use plugin_framework::{Plugin, CORE_ABI_VERSION};
+
+pub struct AbiMismatch;
+
+impl Plugin for AbiMismatch {
+    fn name(&self) -> &'static str {
+        "abi_mismatch"
+    }
+}
+
+#[no_mangle]
+#[allow(non_upper_case_globals)]
+pub static _plugin_abi_version: u32 = CORE_ABI_VERSION + 1;
+
+#[no_mangle]
+#[allow(improper_ctypes_definitions)]
+pub extern "C-unwind" fn _plugin_create() -> *mut dyn Plugin {
+    Box::into_raw(Box::new(AbiMismatch))
+}
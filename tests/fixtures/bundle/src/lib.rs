@@ -0,0 +1,23 @@
+//! A library exporting two plugins through `declare_plugins!`.
+
+use plugin_framework::{declare_plugins, Plugin};
+
+#[derive(Default)]
+pub struct First;
+
+impl Plugin for First {
+    fn name(&self) -> &'static str {
+        "first"
+    }
+}
+
+#[derive(Default)]
+pub struct Second;
+
+impl Plugin for Second {
+    fn name(&self) -> &'static str {
+        "second"
+    }
+}
+
+declare_plugins!((First, First::default), (Second, Second::default));
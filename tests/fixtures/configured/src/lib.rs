@@ -0,0 +1,36 @@
+//! A plugin which takes its version from the configuration it is created with.
+
+use plugin_framework::{Plugin, PluginConfig, CORE_ABI_VERSION};
+
+pub struct Configured {
+    version: String,
+}
+
+impl Plugin for Configured {
+    fn name(&self) -> &'static str {
+        "configured"
+    }
+
+    fn version(&self) -> &str {
+        &self.version
+    }
+}
+
+#[no_mangle]
+#[allow(non_upper_case_globals)]
+pub static _plugin_abi_version: u32 = CORE_ABI_VERSION;
+
+#[no_mangle]
+#[allow(improper_ctypes_definitions)]
+pub extern "C-unwind" fn _plugin_create() -> *mut dyn Plugin {
+    Box::into_raw(Box::new(Configured {
+        version: "unconfigured".to_owned(),
+    }))
+}
+
+#[no_mangle]
+#[allow(improper_ctypes_definitions)]
+pub extern "C-unwind" fn _plugin_create_with_config(config: &PluginConfig) -> *mut dyn Plugin {
+    let version = config.get("version").unwrap_or("unset").to_owned();
+    Box::into_raw(Box::new(Configured { version }))
+}
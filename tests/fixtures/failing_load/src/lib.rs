@@ -0,0 +1,25 @@
+//! A plugin whose `on_plugin_load()` always fails.
+
+use plugin_framework::{Plugin, PluginContext, Result, CORE_ABI_VERSION};
+
+pub struct FailingLoad;
+
+impl Plugin for FailingLoad {
+    fn name(&self) -> &'static str {
+        "failing_load"
+    }
+
+    fn on_plugin_load(&self, _ctx: &mut PluginContext) -> Result<()> {
+        Err("this plugin never loads".into())
+    }
+}
+
+#[no_mangle]
+#[allow(non_upper_case_globals)]
+pub static _plugin_abi_version: u32 = CORE_ABI_VERSION;
+
+#[no_mangle]
+#[allow(improper_ctypes_definitions)]
+pub extern "C-unwind" fn _plugin_create() -> *mut dyn Plugin {
+    Box::into_raw(Box::new(FailingLoad))
+}
@@ -0,0 +1,14 @@
+//! A plugin which loads and unloads without any fuss.
+
+use plugin_framework::{declare_plugin, Plugin};
+
+#[derive(Default)]
+pub struct Hello;
+
+impl Plugin for Hello {
+    fn name(&self) -> &'static str {
+        "hello"
+    }
+}
+
+declare_plugin!(Hello, Hello::default);
@@ -0,0 +1,7 @@
+//! A library with a matching ABI version, but no plugin constructor.
+
+use plugin_framework::CORE_ABI_VERSION;
+
+#[no_mangle]
+#[allow(non_upper_case_globals)]
+pub static _plugin_abi_version: u32 = CORE_ABI_VERSION;
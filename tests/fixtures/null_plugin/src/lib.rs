@@ -0,0 +1,22 @@
+//! A plugin whose constructor returns a null pointer instead of a plugin.
+
+use plugin_framework::{Plugin, CORE_ABI_VERSION};
+use std::ptr;
+
+pub struct NullPlugin;
+
+impl Plugin for NullPlugin {
+    fn name(&self) -> &'static str {
+        "null_plugin"
+    }
+}
+
+#[no_mangle]
+#[allow(non_upper_case_globals)]
+pub static _plugin_abi_version: u32 = CORE_ABI_VERSION;
+
+#[no_mangle]
+#[allow(improper_ctypes_definitions)]
+pub extern "C-unwind" fn _plugin_create() -> *mut dyn Plugin {
+    ptr::null_mut::<NullPlugin>()
+}
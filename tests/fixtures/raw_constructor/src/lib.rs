@@ -0,0 +1,22 @@
+//! A plugin exporting its symbols by hand rather than through `declare_plugin!`, following the
+//! ABI contract documented there.
+
+use plugin_framework::{Plugin, CORE_ABI_VERSION};
+
+pub struct RawConstructor;
+
+impl Plugin for RawConstructor {
+    fn name(&self) -> &'static str {
+        "raw_constructor"
+    }
+}
+
+#[no_mangle]
+#[allow(non_upper_case_globals)]
+pub static _plugin_abi_version: u32 = CORE_ABI_VERSION;
+
+#[no_mangle]
+#[allow(improper_ctypes_definitions)]
+pub extern "C-unwind" fn _plugin_create() -> *mut dyn Plugin {
+    Box::into_raw(Box::new(RawConstructor))
+}
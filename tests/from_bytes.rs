@@ -0,0 +1,14 @@
+mod common;
+
+use plugin_framework::PluginManager;
+use std::fs;
+use std::path::Path;
+
+#[test]
+fn a_plugin_loads_from_the_bytes_of_its_library() {
+    let bytes = fs::read(common::fixture("hello")).unwrap();
+    let mut manager = PluginManager::new();
+    unsafe { manager.load_plugin_from_bytes(&bytes) }.unwrap();
+    assert!(manager.is_loaded("hello"));
+    assert_eq!(manager.plugin_path("hello"), Some(Path::new("<memory>")));
+}
@@ -0,0 +1,19 @@
+mod common;
+
+use plugin_framework::PluginManager;
+
+#[test]
+fn a_group_is_unloaded_together_and_alone() {
+    let mut manager = PluginManager::new();
+    unsafe { manager.load_plugin_in_group(common::fixture("hello"), "greeters") }.unwrap();
+    unsafe { manager.load_plugin(common::fixture("configured")) }.unwrap();
+    assert_eq!(manager.plugins_in_group("greeters"), ["hello"]);
+    assert!(manager.plugins_in_group("nobody").is_empty());
+
+    manager.unload_group("greeters").unwrap();
+    assert!(manager.plugin_names().eq(["configured"]));
+    assert_eq!(manager.library_count(), 1);
+
+    manager.unload_group("greeters").unwrap();
+    assert_eq!(manager.len(), 1);
+}
@@ -0,0 +1,37 @@
+#![cfg(feature = "introspect")]
+
+mod common;
+
+use plugin_framework::{ErrorKind, PluginManager};
+use std::fs;
+
+#[test]
+fn a_plugin_library_lists_its_constructor_and_abi_version() {
+    let symbols = PluginManager::list_symbols(common::fixture("hello")).unwrap();
+    assert!(
+        symbols.iter().any(|s| s == "_plugin_create"),
+        "{:?}",
+        symbols
+    );
+    assert!(
+        symbols.iter().any(|s| s == "_plugin_abi_version"),
+        "{:?}",
+        symbols
+    );
+    assert!(!symbols.iter().any(|s| s == "_plugin_create_all"));
+}
+
+#[test]
+fn a_file_which_isnt_a_library_is_an_error() {
+    let dir = common::temp_dir("introspect");
+    let path = dir.join("notes.txt");
+    fs::write(&path, "not a plugin").unwrap();
+
+    let e = PluginManager::list_symbols(&path).unwrap_err();
+    match e.kind() {
+        ErrorKind::SymbolTableUnreadable(unreadable) => assert_eq!(*unreadable, path),
+        kind => panic!("expected an unreadable symbol table, got {:?}", kind),
+    }
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,140 @@
+mod common;
+
+use plugin_framework::{ErrorKind, PluginManager};
+use std::{env, fs};
+
+#[test]
+fn a_folder_with_one_plugin_loads_one_plugin() {
+    let dir = common::temp_dir("load_plugins");
+    let hello = common::fixture("hello");
+    fs::copy(&hello, dir.join(hello.file_name().unwrap())).unwrap();
+    // Neither of these is a plugin, so both are skipped rather than failing.
+    fs::write(dir.join("notes.txt"), "not a plugin").unwrap();
+    fs::create_dir(dir.join("nested")).unwrap();
+    fs::copy(&hello, dir.join("nested").join(hello.file_name().unwrap())).unwrap();
+
+    let mut manager = PluginManager::new();
+    let report = unsafe { manager.load_plugins(&dir) }.unwrap();
+
+    assert!(report.is_success(), "{:?}", report);
+    assert_eq!(report.loaded, [dir.join(hello.file_name().unwrap())]);
+    assert_eq!(report.plugins, ["hello"]);
+
+    assert!(manager.plugin_names().eq(["hello"]));
+    assert_eq!(
+        manager.loaded_library_paths(),
+        [dir.join(hello.file_name().unwrap())]
+    );
+
+    drop(manager);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn a_folder_loaded_in_parallel_is_loaded_in_path_order() {
+    let dir = common::temp_dir("load_plugins_parallel");
+    let mut copies = Vec::new();
+    for name in ["hello", "configured", "failing_load"] {
+        let fixture = common::fixture(name);
+        let copy = dir.join(fixture.file_name().unwrap());
+        fs::copy(&fixture, &copy).unwrap();
+        copies.push(copy);
+    }
+
+    let mut manager = PluginManager::new();
+    let report = unsafe { manager.load_plugins_parallel(&dir) }.unwrap();
+
+    let failed: Vec<_> = report.failed.iter().map(|(path, _)| path).collect();
+    assert_eq!(failed, [&copies[2]]);
+    assert_eq!(report.loaded, [copies[1].clone(), copies[0].clone()]);
+    assert!(manager.plugin_names().eq(["configured", "hello"]));
+
+    drop(manager);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn folders_on_a_search_path_are_loaded_in_turn() {
+    let dir = common::temp_dir("load_from_env");
+    let hello = common::fixture("hello");
+    let (first, second) = (dir.join("first"), dir.join("second"));
+    for folder in [&first, &second] {
+        fs::create_dir(folder).unwrap();
+        fs::copy(&hello, folder.join(hello.file_name().unwrap())).unwrap();
+    }
+    let search_path = env::join_paths([&first, &dir.join("missing"), &second]).unwrap();
+    env::set_var("PLUGIN_FRAMEWORK_TEST_PATH", search_path);
+
+    let mut manager = PluginManager::new();
+    let report = unsafe { manager.load_from_env("PLUGIN_FRAMEWORK_TEST_PATH") };
+
+    assert_eq!(report.loaded, [first.join(hello.file_name().unwrap())]);
+    assert_eq!(report.failed.len(), 1);
+    assert!(
+        matches!(report.failed[0].1.kind(), ErrorKind::DuplicatePlugin(_)),
+        "{}",
+        report.failed[0].1
+    );
+    assert_eq!(manager.len(), 1);
+
+    drop(manager);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn scanning_a_folder_again_only_loads_new_files() {
+    let dir = common::temp_dir("rescan");
+    let hello = common::fixture("hello");
+    let copy = dir.join(hello.file_name().unwrap());
+    fs::copy(&hello, &copy).unwrap();
+
+    let mut manager = PluginManager::new();
+    let first = unsafe { manager.load_plugins(&dir) }.unwrap();
+    assert_eq!(first.plugins, ["hello"]);
+
+    let second = unsafe { manager.load_plugins(dir.join(".")) }.unwrap();
+    assert!(second.loaded.is_empty(), "{:?}", second);
+    assert!(second.failed.is_empty(), "{:?}", second);
+    assert_eq!(manager.len(), 1);
+
+    drop(manager);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn a_file_loaded_by_another_spelling_is_skipped_by_a_scan() {
+    let dir = common::temp_dir("respelled");
+    let hello = common::fixture("hello");
+    fs::copy(&hello, dir.join(hello.file_name().unwrap())).unwrap();
+
+    let mut manager = PluginManager::new();
+    let respelled = dir.join("..").join(dir.file_name().unwrap());
+    unsafe { manager.load_plugin(respelled.join(hello.file_name().unwrap())) }.unwrap();
+
+    let report = unsafe { manager.load_plugins(&dir) }.unwrap();
+    assert!(report.loaded.is_empty(), "{:?}", report);
+    assert_eq!(manager.len(), 1);
+
+    drop(manager);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn only_files_matching_the_glob_are_loaded() {
+    let dir = common::temp_dir("matching");
+    let hello = common::fixture("hello");
+    fs::copy(&hello, dir.join(hello.file_name().unwrap())).unwrap();
+    let helper = dir.join(common::fixture("raw_constructor").file_name().unwrap());
+    fs::copy(common::fixture("raw_constructor"), helper).unwrap();
+
+    let mut manager = PluginManager::new();
+    let pattern = format!("{}hel*", env::consts::DLL_PREFIX);
+    let report = unsafe { manager.load_plugins_matching(&dir, &pattern) }.unwrap();
+
+    assert!(report.is_success(), "{:?}", report);
+    assert_eq!(report.plugins, ["hello"]);
+    assert_eq!(manager.len(), 1);
+
+    drop(manager);
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,41 @@
+#![cfg(feature = "async")]
+
+mod common;
+
+use plugin_framework::PluginManager;
+use std::fs;
+
+#[test]
+fn a_folder_loads_from_within_a_tokio_runtime() {
+    let dir = common::temp_dir("load_plugins_async");
+    let mut copies = Vec::new();
+    for name in ["hello", "failing_load"] {
+        let fixture = common::fixture(name);
+        let copy = dir.join(fixture.file_name().unwrap());
+        fs::copy(&fixture, &copy).unwrap();
+        copies.push(copy);
+    }
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    let mut manager = PluginManager::new();
+    let report = runtime
+        .block_on(unsafe { manager.load_plugins_async(&dir) })
+        .unwrap();
+
+    let failed: Vec<_> = report.failed.iter().map(|(path, _)| path).collect();
+    assert_eq!(failed, [&copies[1]]);
+    assert_eq!(report.loaded, [copies[0].clone()]);
+    assert!(manager.plugin_names().eq(["hello"]));
+
+    // Loading the same folder again skips what is already loaded.
+    let again = runtime
+        .block_on(unsafe { manager.load_plugins_async(&dir) })
+        .unwrap();
+    assert!(again.loaded.is_empty());
+    assert_eq!(manager.len(), 1);
+
+    drop(manager);
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,106 @@
+mod common;
+
+use plugin_framework::{ErrorKind, PluginManager, PluginMetadata};
+use std::sync::{Arc, RwLock};
+use std::thread;
+
+#[test]
+fn a_loaded_plugin_can_be_looked_up_by_name() {
+    let mut manager = PluginManager::new();
+    unsafe { manager.load_plugin(common::fixture("hello")) }.unwrap();
+
+    assert_eq!(manager.get_plugin("hello").map(|p| p.name()), Some("hello"));
+    assert!(manager.get_plugin_mut("hello").is_some());
+    assert!(manager.get_plugin("goodbye").is_none());
+}
+
+#[test]
+fn loaded_plugins_can_be_iterated_over() {
+    let mut manager = PluginManager::new();
+    assert!(manager.is_empty());
+    unsafe { manager.load_plugin(common::fixture("hello")) }.unwrap();
+
+    let names: Vec<&str> = manager.plugins().map(|p| p.name()).collect();
+    assert_eq!(names, ["hello"]);
+    assert!(manager.plugin_names().eq(["hello"]));
+}
+
+#[test]
+fn a_plugin_without_a_version_reports_unknown() {
+    let mut manager = PluginManager::new();
+    unsafe { manager.load_plugin(common::fixture("hello")) }.unwrap();
+
+    assert_eq!(manager.get_plugin("hello").unwrap().version(), "unknown");
+}
+
+#[test]
+fn a_shared_manager_can_be_read_from_other_threads() {
+    let mut manager = PluginManager::new();
+    unsafe { manager.load_plugin(common::fixture("hello")) }.unwrap();
+    let manager = Arc::new(RwLock::new(manager));
+
+    let readers: Vec<_> = (0..4)
+        .map(|_| {
+            let manager = Arc::clone(&manager);
+            thread::spawn(move || manager.read().unwrap().get_plugin("hello").is_some())
+        })
+        .collect();
+    for reader in readers {
+        assert!(reader.join().unwrap());
+    }
+}
+
+#[test]
+fn a_plugin_can_be_looked_up_and_unloaded_by_id() {
+    let mut manager = PluginManager::new();
+    let id = unsafe { manager.load_plugin(common::fixture("hello")) }.unwrap();
+    assert_eq!(manager.get_plugin_by_id(id).unwrap().name(), "hello");
+
+    manager.unload_by_id(id).unwrap();
+    assert!(manager.get_plugin_by_id(id).is_none());
+    let e = manager.unload_by_id(id).unwrap_err();
+    assert!(matches!(e.kind(), ErrorKind::PluginIdNotFound(_)), "{}", e);
+
+    // Ids aren't reused, even once the plugin they referred to is gone.
+    let reloaded = unsafe { manager.load_plugin(common::fixture("hello")) }.unwrap();
+    assert_ne!(reloaded, id);
+}
+
+#[test]
+fn default_metadata_carries_the_name_and_version() {
+    let mut manager = PluginManager::new();
+    unsafe { manager.load_plugin(common::fixture("hello")) }.unwrap();
+
+    let metadata = manager.list_metadata();
+    assert_eq!(
+        metadata,
+        [PluginMetadata {
+            name: "hello".to_owned(),
+            version: "unknown".to_owned(),
+            ..PluginMetadata::default()
+        }]
+    );
+}
+
+#[test]
+fn a_plugin_remembers_where_it_was_loaded_from() {
+    let mut manager = PluginManager::new();
+    let hello = common::fixture("hello");
+    unsafe { manager.load_plugin(&hello) }.unwrap();
+
+    assert_eq!(manager.plugin_path("hello"), Some(hello.as_path()));
+    assert_eq!(manager.plugin_path("goodbye"), None);
+}
+
+#[test]
+fn a_loaded_plugin_can_be_used_straight_away() {
+    let mut manager = PluginManager::new();
+    let plugin = unsafe { manager.load_plugin_ref(common::fixture("hello")) }.unwrap();
+    assert_eq!(plugin.name(), "hello");
+    assert!(manager.is_loaded("hello"));
+
+    let Err(e) = (unsafe { manager.load_plugin_ref(common::fixture("missing_symbol")) }) else {
+        panic!("a library without a constructor loaded");
+    };
+    assert!(matches!(e.kind(), ErrorKind::SymbolNotFound(_)), "{}", e);
+}
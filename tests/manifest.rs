@@ -0,0 +1,72 @@
+#![cfg(feature = "manifest")]
+
+mod common;
+
+use plugin_framework::{ErrorKind, PluginManager};
+use std::fs;
+
+#[test]
+fn a_manifest_loads_its_enabled_entries_in_order() {
+    let dir = common::temp_dir("manifest");
+    let manifest = dir.join("plugins.toml");
+    fs::write(
+        &manifest,
+        format!(
+            "[[plugin]]\n\
+             name = 'hello'\n\
+             path = '{hello}'\n\
+             \n\
+             [[plugin]]\n\
+             name = 'configured'\n\
+             path = '{configured}'\n\
+             enabled = false\n\
+             \n\
+             [[plugin]]\n\
+             name = 'impostor'\n\
+             path = '{configured}'\n\
+             \n\
+             [[plugin]]\n\
+             name = 'gone'\n\
+             path = 'gone.so'\n",
+            hello = common::fixture("hello").display(),
+            configured = common::fixture("configured").display(),
+        ),
+    )
+    .unwrap();
+
+    let mut manager = PluginManager::new();
+    let report = unsafe { manager.load_from_manifest(&manifest) }.unwrap();
+
+    assert_eq!(report.loaded, [common::fixture("hello")]);
+    assert_eq!(report.plugins, ["hello"]);
+    let failures: Vec<_> = report.failed.iter().map(|(_, e)| e.kind()).collect();
+    assert!(
+        matches!(
+            failures[..],
+            [
+                ErrorKind::ManifestEntryMismatch(..),
+                ErrorKind::ManifestEntryMissing(..)
+            ]
+        ),
+        "{:?}",
+        report
+    );
+    assert!(manager.plugin_names().eq(["hello"]));
+
+    drop(manager);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn an_invalid_manifest_loads_nothing() {
+    let dir = common::temp_dir("manifest_invalid");
+    let manifest = dir.join("plugins.toml");
+    fs::write(&manifest, "[[plugin]]\nname = 'hello'\n").unwrap();
+
+    let mut manager = PluginManager::new();
+    let e = unsafe { manager.load_from_manifest(&manifest) }.unwrap_err();
+    assert!(matches!(e.kind(), ErrorKind::ManifestInvalid(_)), "{}", e);
+    assert!(manager.is_empty());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
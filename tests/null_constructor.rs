@@ -0,0 +1,13 @@
+mod common;
+
+use plugin_framework::{ErrorKind, PluginManager};
+
+#[test]
+fn a_null_plugin_is_rejected() {
+    let mut manager = PluginManager::new();
+    let e = unsafe { manager.load_plugin(common::fixture("null_plugin")) }.unwrap_err();
+
+    assert!(matches!(e.kind(), ErrorKind::ConstructorReturnedNull(_)));
+    assert_eq!(manager.len(), 0);
+    assert!(manager.loaded_library_paths().is_empty());
+}
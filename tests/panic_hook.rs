@@ -0,0 +1,43 @@
+//! The panic hook is process wide, so this lives in a test binary of its own.
+
+use plugin_framework::{Plugin, PluginManager};
+use std::panic::{self, PanicHookInfo};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+type Hook = Box<dyn Fn(&PanicHookInfo<'_>) + Send + Sync + 'static>;
+
+struct Panicky;
+
+impl Plugin for Panicky {
+    fn name(&self) -> &'static str {
+        "panicky"
+    }
+
+    fn update(&mut self, _delta: Duration) {
+        panic!("update failed");
+    }
+}
+
+fn address(hook: &Hook) -> usize {
+    &**hook as *const _ as *const () as usize
+}
+
+#[test]
+fn the_host_hook_still_runs_and_is_put_back_afterwards() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let counted = Arc::clone(&calls);
+    let hook: Hook = Box::new(move |_| {
+        counted.fetch_add(1, Ordering::SeqCst);
+    });
+    let host_hook = address(&hook);
+    panic::set_hook(hook);
+
+    let mut manager = PluginManager::new();
+    manager.register_plugin(Box::new(Panicky)).unwrap();
+    manager.update_all(Duration::ZERO);
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+    assert_eq!(address(&panic::take_hook()), host_hook);
+}
@@ -0,0 +1,78 @@
+mod common;
+
+use plugin_framework::{ErrorKind, PluginManager};
+use std::fs::{self, File};
+use std::time::Duration;
+
+#[test]
+fn a_reloaded_plugin_keeps_its_name_and_id() {
+    let hello = common::fixture("hello");
+    let mut manager = PluginManager::new();
+    let id = unsafe { manager.load_plugin(&hello) }.unwrap();
+
+    unsafe { manager.reload_plugin("hello", &hello) }.unwrap();
+
+    assert_eq!(manager.get_plugin_by_id(id).unwrap().name(), "hello");
+    assert!(manager.plugin_names().eq(["hello"]));
+    assert_eq!(manager.library_count(), 1);
+}
+
+#[test]
+fn a_failed_reload_says_the_plugin_is_gone() {
+    let mut manager = PluginManager::new();
+    unsafe { manager.load_plugin(common::fixture("hello")) }.unwrap();
+
+    let e =
+        unsafe { manager.reload_plugin("hello", common::fixture("missing_symbol")) }.unwrap_err();
+    assert!(
+        matches!(e.kind(), ErrorKind::ReloadFailed(name) if name == "hello"),
+        "{}",
+        e
+    );
+    assert!(manager.is_empty());
+    assert_eq!(manager.library_count(), 0);
+}
+
+#[test]
+fn reloading_everything_keeps_ids_and_leaves_in_memory_plugins_alone() {
+    let hello = common::fixture("hello");
+    let mut manager = PluginManager::new();
+    manager.set_allow_duplicate_names(true);
+    let id = unsafe { manager.load_plugin(&hello) }.unwrap();
+    let bytes = fs::read(&hello).unwrap();
+    let in_memory = unsafe { manager.load_plugin_from_bytes(&bytes) }.unwrap();
+
+    unsafe { manager.reload_all() }.unwrap();
+
+    assert_eq!(manager.len(), 2);
+    assert_eq!(manager.get_plugin_by_id(id).unwrap().name(), "hello");
+    assert_eq!(manager.get_plugin_by_id(in_memory).unwrap().name(), "hello");
+}
+
+#[test]
+fn only_changed_files_are_reloaded() {
+    let dir = common::temp_dir("reload_changed");
+    let hello = common::fixture("hello");
+    let copy = dir.join(hello.file_name().unwrap());
+    fs::copy(&hello, &copy).unwrap();
+
+    let mut manager = PluginManager::new();
+    let id = unsafe { manager.load_plugin(&copy) }.unwrap();
+    assert!(unsafe { manager.reload_changed() }.unwrap().is_empty());
+
+    let later = fs::metadata(&copy).unwrap().modified().unwrap() + Duration::from_secs(1);
+    File::options()
+        .write(true)
+        .open(&copy)
+        .unwrap()
+        .set_modified(later)
+        .unwrap();
+    assert_eq!(unsafe { manager.reload_changed() }.unwrap(), ["hello"]);
+    assert_eq!(manager.get_plugin_by_id(id).unwrap().name(), "hello");
+
+    // The new modification time is remembered, so it isn't reloaded again.
+    assert!(unsafe { manager.reload_changed() }.unwrap().is_empty());
+
+    drop(manager);
+    fs::remove_dir_all(&dir).unwrap();
+}
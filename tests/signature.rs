@@ -0,0 +1,37 @@
+#![cfg(feature = "signature")]
+
+mod common;
+
+use ed25519_dalek::{Signer, SigningKey};
+use plugin_framework::{ErrorKind, PluginManager};
+use std::fs;
+
+#[test]
+fn a_signed_plugin_loads_until_it_is_tampered_with() {
+    let key = SigningKey::from_bytes(&[7; 32]);
+    let mut library = fs::read(common::fixture("hello")).unwrap();
+    let signature = key.sign(&library).to_bytes();
+
+    let dir = common::temp_dir("signature");
+    let path = dir.join(common::fixture("hello").file_name().unwrap());
+    fs::write(&path, &library).unwrap();
+
+    let mut manager = PluginManager::new();
+    unsafe { manager.load_plugin_signed(&path, &signature, &key.verifying_key()) }.unwrap();
+    assert!(manager.is_loaded("hello"));
+    let e =
+        unsafe { manager.load_plugin_signed(&path, &signature, &key.verifying_key()) }.unwrap_err();
+    assert!(matches!(e.kind(), ErrorKind::AlreadyLoaded(_)), "{}", e);
+    assert_eq!(manager.library_count(), 1);
+    manager.unload().unwrap();
+
+    let last = library.len() - 1;
+    library[last] ^= 1;
+    fs::write(&path, &library).unwrap();
+    let e =
+        unsafe { manager.load_plugin_signed(&path, &signature, &key.verifying_key()) }.unwrap_err();
+    assert!(matches!(e.kind(), ErrorKind::SignatureInvalid(_)), "{}", e);
+    assert!(manager.is_empty());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
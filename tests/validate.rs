@@ -0,0 +1,16 @@
+mod common;
+
+use plugin_framework::{ErrorKind, PluginManager};
+
+#[test]
+fn a_valid_plugin_reports_its_metadata() {
+    let metadata = unsafe { PluginManager::validate_plugin(common::fixture("hello")) }.unwrap();
+    assert_eq!(metadata.name, "hello");
+}
+
+#[test]
+fn a_library_without_a_constructor_is_invalid() {
+    let e =
+        unsafe { PluginManager::validate_plugin(common::fixture("missing_symbol")) }.unwrap_err();
+    assert!(matches!(e.kind(), ErrorKind::SymbolNotFound(_)), "{}", e);
+}
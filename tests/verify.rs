@@ -0,0 +1,52 @@
+#![cfg(feature = "verify")]
+
+mod common;
+
+use plugin_framework::{ErrorKind, PluginManager};
+use sha2::{Digest, Sha256};
+use std::fmt::Write;
+use std::fs;
+
+fn sha256(path: &std::path::Path) -> String {
+    Sha256::digest(fs::read(path).unwrap())
+        .iter()
+        .fold(String::new(), |mut hex, byte| {
+            let _ = write!(hex, "{:02x}", byte);
+            hex
+        })
+}
+
+#[test]
+fn a_plugin_with_the_expected_checksum_loads() {
+    let hello = common::fixture("hello");
+    let mut manager = PluginManager::new();
+    unsafe { manager.load_plugin_verified(&hello, &sha256(&hello).to_uppercase()) }.unwrap();
+    assert!(manager.is_loaded("hello"));
+}
+
+#[test]
+fn a_mismatched_checksum_is_rejected() {
+    let hello = common::fixture("hello");
+    let mut manager = PluginManager::new();
+    let e = unsafe { manager.load_plugin_verified(&hello, &"0".repeat(64)) }.unwrap_err();
+    assert!(
+        matches!(e.kind(), ErrorKind::IntegrityCheckFailed(..)),
+        "{}",
+        e
+    );
+    assert!(manager.is_empty());
+}
+
+#[test]
+fn a_verified_file_only_loads_once() {
+    let hello = common::fixture("hello");
+    let checksum = sha256(&hello);
+    let mut manager = PluginManager::new();
+    unsafe { manager.load_plugin_verified(&hello, &checksum) }.unwrap();
+
+    let e = unsafe { manager.load_plugin_verified(&hello, &checksum) }.unwrap_err();
+    assert!(matches!(e.kind(), ErrorKind::AlreadyLoaded(_)), "{}", e);
+    let e = unsafe { manager.load_plugin(&hello) }.unwrap_err();
+    assert!(matches!(e.kind(), ErrorKind::AlreadyLoaded(_)), "{}", e);
+    assert_eq!(manager.library_count(), 1);
+}
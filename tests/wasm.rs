@@ -0,0 +1,118 @@
+#![cfg(feature = "wasm")]
+
+mod common;
+
+use plugin_framework::{ErrorKind, PluginManager};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Append a section with the given `id` and `contents` to `module`. Every section used here is
+/// short enough for its length to fit in a single byte.
+fn section(module: &mut Vec<u8>, id: u8, contents: &[u8]) {
+    module.push(id);
+    module.push(contents.len() as u8);
+    module.extend_from_slice(contents);
+}
+
+fn export(exports: &mut Vec<u8>, name: &str, kind: u8, index: u8) {
+    exports.push(name.len() as u8);
+    exports.extend_from_slice(name.as_bytes());
+    exports.extend_from_slice(&[kind, index]);
+}
+
+/// Assemble a WebAssembly plugin called `name` (which has to be shorter than 64 bytes), whose
+/// `on_plugin_load` returns `load_code`, and write it to `dir`.
+fn write_module(dir: &Path, name: &str, load_code: u8) -> PathBuf {
+    assert!(name.len() < 64 && load_code < 64);
+    let mut module = b"\0asm\x01\0\0\0".to_vec();
+    // Two signatures, `() -> i64` and `() -> i32`.
+    section(&mut module, 1, &[2, 0x60, 0, 1, 0x7e, 0x60, 0, 1, 0x7f]);
+    // `plugin_name`, `on_plugin_load` and `on_plugin_unload`.
+    section(&mut module, 3, &[3, 0, 1, 1]);
+    // One page of memory.
+    section(&mut module, 5, &[1, 0, 1]);
+
+    let mut exports = vec![4];
+    export(&mut exports, "memory", 2, 0);
+    export(&mut exports, "plugin_name", 0, 0);
+    export(&mut exports, "on_plugin_load", 0, 1);
+    export(&mut exports, "on_plugin_unload", 0, 2);
+    section(&mut module, 7, &exports);
+
+    // The name is at address zero, so `plugin_name` only has to return its length.
+    let len = name.len() as u8;
+    let code = [
+        3, 4, 0, 0x42, len, 0x0b, 4, 0, 0x41, load_code, 0x0b, 4, 0, 0x41, 0, 0x0b,
+    ];
+    section(&mut module, 10, &code);
+
+    let mut data = vec![1, 0, 0x41, 0, 0x0b, len];
+    data.extend_from_slice(name.as_bytes());
+    section(&mut module, 11, &data);
+
+    let path = dir.join(format!("{}.wasm", name));
+    fs::write(&path, module).unwrap();
+    path
+}
+
+#[test]
+fn a_wasm_plugin_loads_and_unloads_like_a_native_one() {
+    let dir = common::temp_dir("wasm_load");
+    let path = write_module(&dir, "sandboxed", 0);
+
+    let mut manager = PluginManager::new();
+    let id = unsafe { manager.load_plugin(&path) }.unwrap();
+    assert_eq!(manager.get_plugin_by_id(id).unwrap().name(), "sandboxed");
+    assert!(manager.get_plugin("sandboxed").is_some());
+
+    manager.unload().unwrap();
+    assert!(manager.is_empty());
+    assert!(manager.loaded_library_paths().is_empty());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn a_failing_wasm_load_hook_is_an_error() {
+    let dir = common::temp_dir("wasm_failing");
+    let path = write_module(&dir, "refusing", 1);
+
+    let mut manager = PluginManager::new();
+    let e = unsafe { manager.load_plugin(&path) }.unwrap_err();
+    assert!(matches!(e.kind(), ErrorKind::PluginLoadFailed(..)), "{}", e);
+    assert!(manager.is_empty());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn wasm_plugins_are_picked_up_from_a_folder() {
+    let dir = common::temp_dir("wasm_folder");
+    let module = write_module(&dir, "sandboxed", 0);
+    let hello = common::fixture("hello");
+    fs::copy(&hello, dir.join(hello.file_name().unwrap())).unwrap();
+
+    let mut manager = PluginManager::new();
+    let report = unsafe { manager.load_plugins(&dir) }.unwrap();
+    assert!(report.is_success(), "{:?}", report);
+    assert!(report.loaded.contains(&module));
+    let mut names: Vec<&str> = manager.plugin_names().collect();
+    names.sort_unstable();
+    assert_eq!(names, ["hello", "sandboxed"]);
+
+    drop(manager);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn a_file_which_isnt_a_wasm_module_fails_to_load() {
+    let dir = common::temp_dir("wasm_invalid");
+    let path = dir.join("broken.wasm");
+    fs::write(&path, "not a module").unwrap();
+
+    let mut manager = PluginManager::new();
+    let e = unsafe { manager.load_plugin(&path) }.unwrap_err();
+    assert!(matches!(e.kind(), ErrorKind::LibraryLoadFailed(_)), "{}", e);
+
+    fs::remove_dir_all(&dir).unwrap();
+}